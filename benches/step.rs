@@ -0,0 +1,210 @@
+//! Measures how long a simulation tick of `World::step` takes with hundreds of enemies, and
+//! how the `SpatialGrid` broad phase compares to checking every bullet against every enemy.
+//!
+//! Run with `cargo bench`. Level files are limited to 8x15 enemies of a fixed size, so the
+//! larger crowds are built directly with smaller enemies. Every scenario keeps the player
+//! bullets at a fixed number of piercing shots spread over the width of the playfield, with
+//! diving and shooting enemies. The enemies have too many hit points to be destroyed and the
+//! player can't be hit, so the formation stays full. After every tick the bullet/enemy check
+//! is timed once through the grid and once naively on the same state, both have to find the
+//! same hits. The timings are only printed, they depend too much on the machine to fail on.
+
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+use macroquad::prelude::*;
+use macroquad::rand::RandGenerator;
+use codeinvaders::bullet::{Bullet, Owner};
+use codeinvaders::dive::DivePattern;
+use codeinvaders::enemy::{Enemy, EnemyKind};
+use codeinvaders::formation::Formation;
+use codeinvaders::game_mode::Difficulty;
+use codeinvaders::input::Input;
+use codeinvaders::level::Level;
+use codeinvaders::spatial_grid::SpatialGrid;
+use codeinvaders::timestep::FixedTimestep;
+use codeinvaders::world::{StepOutcome, World, PADDING, PLAYER_TEXTURE, PLAYFIELD};
+
+const ENEMY_TEXTURE: &str = "enemy.png";
+const ENEMY_TEXTURE_SIZE: Vec2 = Vec2::new(64.0, 48.0);
+const TICKS: u32 = 1200;
+const PLAYER_BULLETS: usize = 64;
+/// Formations of `(rows, columns)`, the first one is the largest a level file allows
+const FORMATIONS: [(usize, usize); 4] = [(8, 15), (12, 30), (16, 40), (20, 50)];
+
+fn enemy_kind() -> EnemyKind {
+    let mut kind = EnemyKind::with_texture(ENEMY_TEXTURE);
+    kind.hit_points = u32::MAX;
+    kind
+}
+
+/// A level whose enemies shoot and dive a lot, the formation is replaced by `fill_formation`
+fn level() -> Level {
+    Level {
+        name: "Benchmark".to_string(),
+        texture: ENEMY_TEXTURE.to_string(),
+        speed_multiplier: 0.5,
+        fire_rate: 10.0,
+        enemy_types: HashMap::from([('E', enemy_kind())]),
+        formation: vec!["E".to_string()],
+        dive_patterns: vec![DivePattern::Swoop, DivePattern::Loop, DivePattern::Zigzag, DivePattern::Kamikaze],
+        dive_rate: 1.0,
+        boss: false,
+        file_hash: [0; 32],
+    }
+}
+
+fn new_world() -> World {
+    let texture_sizes = HashMap::from([
+        (PLAYER_TEXTURE.to_string(), vec2(64.0, 64.0)),
+        (ENEMY_TEXTURE.to_string(), ENEMY_TEXTURE_SIZE),
+    ]);
+    World::new(PLAYFIELD, texture_sizes, HashMap::new(), vec![level()])
+}
+
+/// Replaces the enemies with a formation of `rows` x `columns` enemies small enough to fit
+/// into three quarters of the width, so it still has room to march
+fn fill_formation(world: &mut World, rows: usize, columns: usize) {
+    let pitch = PLAYFIELD.x * 0.75 / columns as f32;
+    let enemy_width = pitch * 0.7;
+    let scale = enemy_width / ENEMY_TEXTURE_SIZE.x;
+    let spacing = vec2(pitch, ENEMY_TEXTURE_SIZE.y * scale + PADDING / 2.0);
+
+    let kind = Rc::new(enemy_kind());
+    world.enemies = (0..rows)
+        .flat_map(|row| (0..columns).map(move |column| (column, row)))
+        .map(|slot| Enemy::new(Rc::clone(&kind), ENEMY_TEXTURE_SIZE, scale, slot))
+        .collect();
+    let origin = vec2(PADDING + 1.0, PLAYFIELD.y / 10.0);
+    let speed = PLAYFIELD.x / 25.0 * 0.5;
+    world.formation = Formation::new(origin, spacing, enemy_width, speed, world.enemies.len(), PLAYFIELD);
+}
+
+fn start(world: &mut World, rows: usize, columns: usize) {
+    world.new_game(42, 1, Difficulty::Hard);
+    world.player.weapon.max_bullets = PLAYER_BULLETS;
+    fill_formation(world, rows, columns);
+}
+
+/// Fires piercing bullets from below the formation until `PLAYER_BULLETS` are on their way
+fn refill_bullets(world: &mut World, rng: &RandGenerator) {
+    let missing = PLAYER_BULLETS.saturating_sub(world.player_bullets.iter().count());
+    for _ in 0..missing {
+        let position = vec2(rng.gen_range(0.0, PLAYFIELD.x), PLAYFIELD.y * 0.8);
+        let mut bullet = Bullet::new(position, Owner::Player);
+        bullet.piercing = true;
+        if world.player_bullets.insert(bullet).is_none() {
+            break;
+        }
+    }
+}
+
+/// Checks every bullet against every enemy
+fn naive(bullets: &[&Bullet], enemies: &[(Vec2, Vec2)]) -> usize {
+    let mut hits = 0;
+    for bullet in bullets {
+        for &(position, size) in enemies {
+            if bullet.impact(PLAYFIELD, position, size).is_some() {
+                hits += 1;
+            }
+        }
+    }
+    hits
+}
+
+/// Checks every bullet only against the enemies in the grid cells it passed, like `World::step`
+fn grid(bullets: &[&Bullet], enemies: &[(Vec2, Vec2)], grid: &mut SpatialGrid, candidates: &mut Vec<usize>) -> usize {
+    grid.clear();
+    for (index, &(position, size)) in enemies.iter().enumerate() {
+        grid.insert(index, position, size);
+    }
+
+    let mut hits = 0;
+    for bullet in bullets {
+        let (bounds_position, bounds_size) = bullet.swept_bounds(PLAYFIELD);
+        grid.query(bounds_position, bounds_size, candidates);
+        for &index in candidates.iter() {
+            let (position, size) = enemies[index];
+            if bullet.impact(PLAYFIELD, position, size).is_some() {
+                hits += 1;
+            }
+        }
+    }
+    hits
+}
+
+fn time<T>(f: impl FnOnce() -> T) -> (T, Duration) {
+    let start = Instant::now();
+    let result = black_box(f());
+    (result, start.elapsed())
+}
+
+struct Timings {
+    step: Duration,
+    longest_step: Duration,
+    naive: Duration,
+    grid: Duration,
+}
+
+fn measure(rows: usize, columns: usize) -> Timings {
+    let rng = RandGenerator::new();
+    rng.srand(7);
+    let mut world = new_world();
+    start(&mut world, rows, columns);
+    // The same grid as the one of the world
+    let mut spatial_grid = SpatialGrid::new(PLAYFIELD, PLAYFIELD.x / 16.0);
+    let mut candidates = Vec::new();
+
+    let mut timings = Timings { step: Duration::ZERO, longest_step: Duration::ZERO, naive: Duration::ZERO, grid: Duration::ZERO };
+    for _ in 0..TICKS {
+        refill_bullets(&mut world, &rng);
+        world.player.invulnerable = f32::MAX;
+
+        let (outcome, elapsed) = time(|| world.step(&Input::default(), FixedTimestep::TICK));
+        timings.step += elapsed;
+        timings.longest_step = timings.longest_step.max(elapsed);
+
+        let bullets: Vec<&Bullet> = world.player_bullets.iter().collect();
+        let enemies: Vec<(Vec2, Vec2)> = world.enemies.iter().map(|enemy| (enemy.position(&world.formation), enemy.size())).collect();
+        let (naive_hits, elapsed) = time(|| naive(&bullets, &enemies));
+        timings.naive += elapsed;
+        let (grid_hits, elapsed) = time(|| grid(&bullets, &enemies, &mut spatial_grid, &mut candidates));
+        timings.grid += elapsed;
+        assert_eq!(naive_hits, grid_hits, "the grid must find the same hits as the naive check");
+
+        // The formation marched down to the player, start over
+        if outcome != StepOutcome::Running {
+            start(&mut world, rows, columns);
+        }
+    }
+
+    Timings {
+        step: timings.step / TICKS,
+        longest_step: timings.longest_step,
+        naive: timings.naive / TICKS,
+        grid: timings.grid / TICKS,
+    }
+}
+
+fn main() {
+    let budget = Duration::from_secs_f32(FixedTimestep::TICK);
+    println!("{} ticks per scenario with {} player bullets, a tick lasts {:?}", TICKS, PLAYER_BULLETS, budget);
+    println!(
+        "{:>8} {:>12} {:>12} {:>8} {:>12} {:>12} {:>8}",
+        "enemies", "step/tick", "max step", "budget", "naive/tick", "grid/tick", "speedup",
+    );
+    for (rows, columns) in FORMATIONS {
+        let timings = measure(rows, columns);
+        println!(
+            "{:>8} {:>12?} {:>12?} {:>7.2}% {:>12?} {:>12?} {:>7.1}x",
+            rows * columns,
+            timings.step,
+            timings.longest_step,
+            timings.step.as_secs_f64() / budget.as_secs_f64() * 100.0,
+            timings.naive,
+            timings.grid,
+            timings.naive.as_secs_f64() / timings.grid.as_secs_f64(),
+        );
+    }
+}
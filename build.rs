@@ -1,18 +1,17 @@
 use std::env;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-fn main() {
-    // OUT_DIR is automatically set by cargo and contains the build directory path
-    let out_path = PathBuf::from(env::var("OUT_DIR").unwrap());
-    let assets_dir = PathBuf::from("assets");
+// Copies all files of `directory` next to the build output, so the game finds them at runtime
+fn copy_directory(out_path: &Path, directory: &str) {
+    let source_dir = PathBuf::from(directory);
 
     // Create the destination directory in the build output
-    let dest_path = out_path.join("../../../assets");
+    let dest_path = out_path.join("../../..").join(directory);
     fs::create_dir_all(&dest_path).unwrap();
 
-    // Iterate over all files in the assets directory and copy them to the destination
-    for entry in fs::read_dir(assets_dir).unwrap() {
+    // Iterate over all files in the directory and copy them to the destination
+    for entry in fs::read_dir(source_dir).unwrap() {
         let entry = entry.unwrap();
         let path = entry.path();
         if path.is_file() {
@@ -21,6 +20,14 @@ fn main() {
         }
     }
 
-    // Tell cargo to re-run the build script whenever any file in the assets directory changes
-    println!("cargo:rerun-if-changed=assets/*");
-}
\ No newline at end of file
+    // Tell cargo to re-run the build script whenever any file in the directory changes
+    println!("cargo:rerun-if-changed={directory}");
+}
+
+fn main() {
+    // OUT_DIR is automatically set by cargo and contains the build directory path
+    let out_path = PathBuf::from(env::var("OUT_DIR").unwrap());
+
+    copy_directory(&out_path, "assets");
+    copy_directory(&out_path, "levels");
+}
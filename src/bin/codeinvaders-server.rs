@@ -0,0 +1,68 @@
+//! A shared leaderboard for the local network. Start it on one machine and run the game with
+//! `--server http://<address>:7878` everywhere else to submit and show the scores there.
+use std::path::PathBuf;
+use std::process;
+use tiny_http::Server;
+use codeinvaders::data_dir;
+use codeinvaders::high_scores::HighScores;
+use codeinvaders::leaderboard_server;
+
+const USAGE: &str = "Usage: codeinvaders-server [--bind <address>] [--data-dir <directory>] [--table-length <entries>]";
+const DEFAULT_ADDRESS: &str = "0.0.0.0:7878";
+
+struct Options {
+    address: String,
+    data_dir: Option<PathBuf>,
+    table_length: usize,
+}
+
+fn parse(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        address: DEFAULT_ADDRESS.to_string(),
+        data_dir: None,
+        table_length: HighScores::DEFAULT_TABLE_LENGTH,
+    };
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--bind" => options.address = args.next().ok_or("--bind needs an address")?,
+            "--data-dir" => {
+                let path = args.next().ok_or("--data-dir needs a directory")?;
+                options.data_dir = Some(PathBuf::from(path));
+            }
+            "--table-length" => {
+                options.table_length = args
+                    .next()
+                    .and_then(|length| length.parse::<usize>().ok())
+                    .filter(|&length| length > 0)
+                    .ok_or("--table-length needs a number of entries greater than 0")?;
+            }
+            _ => return Err(format!("Unknown argument '{arg}'")),
+        }
+    }
+
+    Ok(options)
+}
+
+fn main() {
+    let options = parse(std::env::args().skip(1)).unwrap_or_else(|error| {
+        eprintln!("{error}\n{USAGE}");
+        process::exit(2);
+    });
+
+    // A directory of its own, so the server doesn't mix its scores with the local ones of a
+    // game on the same machine
+    let data_dir = options.data_dir.unwrap_or_else(|| data_dir::resolve(None).join("server"));
+    let mut high_scores = HighScores::new(&data_dir, options.table_length);
+    if let Err(e) = high_scores.load() {
+        eprintln!("Error loading high scores: {}", e);
+    }
+
+    let server = Server::http(&options.address).unwrap_or_else(|error| {
+        eprintln!("Problem starting the server on {}: {error}", options.address);
+        process::exit(1);
+    });
+    println!("Leaderboard server listening on http://{}, saving to {}", server.server_addr(), data_dir.display());
+
+    leaderboard_server::run(&server, &mut high_scores);
+}
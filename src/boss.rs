@@ -0,0 +1,140 @@
+use macroquad::prelude::*;
+use crate::bullet::{Bullet, Owner};
+
+/// The attack phases of the boss, it gets more aggressive the more damage it takes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Phase {
+    /// Sweeps from wall to wall and fires single shots at the player
+    Sweep,
+    /// Additionally bobs up and down and fires a fan of three bullets
+    Wave,
+    /// Moves fast, fires a fan of five bullets and aims at the player
+    Rage,
+}
+
+/// A big enemy which needs many hits to be destroyed. Appears instead of the formation on boss levels.
+pub struct Boss {
+    pub texture: String,
+    pub texture_size: Vec2,
+    pub scale: f32,
+    pub position: Vec2,
+    pub previous_position: Vec2,
+    pub health: u32,
+    pub max_health: u32,
+    pub phase: Phase,
+    /// Seconds the boss still flashes after being hit
+    pub hit_flash: f32,
+    base_y: f32,
+    moving_right: bool,
+    time: f32,
+    fire_timer: f32,
+}
+
+impl Boss {
+    pub fn new(texture: &str, texture_size: Vec2, max_health: u32, playfield: Vec2) -> Self {
+        let scale = playfield.x / 5.0 / texture_size.x;
+        let size = texture_size * scale;
+        let position = vec2(playfield.x / 2.0 - size.x / 2.0, playfield.y / 20.0);
+
+        Self {
+            texture: texture.to_string(),
+            texture_size,
+            scale,
+            position,
+            previous_position: position,
+            health: max_health,
+            max_health,
+            phase: Phase::Sweep,
+            hit_flash: 0.0,
+            base_y: position.y,
+            moving_right: true,
+            time: 0.0,
+            fire_timer: 1.0,
+        }
+    }
+
+    pub fn size(&self) -> Vec2 {
+        self.texture_size * self.scale
+    }
+
+    pub fn is_destroyed(&self) -> bool {
+        self.health == 0
+    }
+
+    /// Takes one hit and returns `true` if this started a new phase
+    pub fn hit(&mut self) -> bool {
+        self.health = self.health.saturating_sub(1);
+        self.hit_flash = 0.1;
+
+        let phase = match self.health * 3 / self.max_health {
+            2.. => Phase::Sweep,
+            1 => Phase::Wave,
+            _ => Phase::Rage,
+        };
+        let phase_changed = phase != self.phase;
+        self.phase = phase;
+        phase_changed
+    }
+
+    /// Moves the boss and returns the bullets it fires at `target`.
+    pub fn update(&mut self, delta_time: f32, playfield: Vec2, target: Vec2) -> Vec<Bullet> {
+        self.previous_position = self.position;
+        self.time += delta_time;
+        self.hit_flash = (self.hit_flash - delta_time).max(0.0);
+
+        let speed = match self.phase {
+            Phase::Sweep | Phase::Wave => playfield.x / 6.0,
+            Phase::Rage => playfield.x / 3.0,
+        };
+        if self.moving_right {
+            self.position.x += speed * delta_time;
+        } else {
+            self.position.x -= speed * delta_time;
+        }
+        if self.position.x + self.size().x >= playfield.x {
+            self.moving_right = false;
+        }
+        if self.position.x <= 0.0 {
+            self.moving_right = true;
+        }
+
+        self.position.y = match self.phase {
+            Phase::Sweep => self.base_y,
+            Phase::Wave | Phase::Rage => self.base_y + (self.time * 2.0).sin().abs() * playfield.y / 8.0,
+        };
+
+        self.fire_timer -= delta_time;
+        if self.fire_timer > 0.0 {
+            return Vec::new();
+        }
+
+        let muzzle = self.position + vec2(self.size().x / 2.0, self.size().y);
+        let aimed = (target - muzzle).try_normalize().unwrap_or(Vec2::Y);
+        let (interval, directions) = match self.phase {
+            Phase::Sweep => (1.2, vec![aimed]),
+            Phase::Wave => (1.0, fan(Vec2::Y, 3)),
+            Phase::Rage => (0.8, [fan(Vec2::Y, 5), vec![aimed]].concat()),
+        };
+        self.fire_timer = interval;
+
+        directions
+            .into_iter()
+            .map(|direction| {
+                let mut bullet = Bullet::new(muzzle, Owner::Enemy);
+                bullet.direction = direction;
+                bullet
+            })
+            .collect()
+    }
+}
+
+/// `count` directions spread evenly around `center`
+fn fan(center: Vec2, count: usize) -> Vec<Vec2> {
+    let spread = 0.3;
+    (0..count)
+        .map(|index| {
+            let angle = (index as f32 - (count - 1) as f32 / 2.0) * spread;
+            Vec2::from_angle(angle).rotate(center)
+        })
+        .collect()
+}
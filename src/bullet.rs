@@ -1,26 +1,74 @@
 use macroquad::prelude::*;
+use crate::collision::swept_circle_rect;
+
+/// Who fired a bullet. Player bullets fly upwards, enemy bullets downwards.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Owner {
+    Player,
+    Enemy,
+}
 
 pub struct Bullet {
     pub position: Vec2,
+    /// Where the bullet was before the last tick, it hits everything it swept over since
+    pub previous_position: Vec2,
+    pub owner: Owner,
+    /// Unit vector of the flight direction
+    pub direction: Vec2,
+    /// A piercing bullet isn't stopped by the enemies it hits
+    pub piercing: bool,
+    /// Set once the bullet has hit something, to count it for the accuracy only once
+    pub has_hit: bool,
     pub collided: bool,
 }
 
 impl Bullet {
-    pub fn new(position: Vec2) -> Self {
+    pub fn new(position: Vec2, owner: Owner) -> Self {
         Self {
             position,
+            previous_position: position,
+            owner,
+            direction: match owner {
+                Owner::Player => Vec2::NEG_Y,
+                Owner::Enemy => Vec2::Y,
+            },
+            piercing: false,
+            has_hit: false,
             collided: false,
         }
     }
 
-    pub fn draw(&self) {
-        // ToDo: Bullet size calculation creates a cheat: First start game & then resize window to make bullet size bigger
-        draw_circle(self.position.x, self.position.y, screen_width() / 400.0, WHITE);
+    /// Bullets are circles with a radius relative to the playfield width
+    pub fn radius(playfield: Vec2) -> f32 {
+        playfield.x / 400.0
+    }
+
+    /// Where on its way during the last tick the bullet first touched the rectangle,
+    /// as a fraction between 0 and 1, so fast bullets can't skip over small targets
+    pub fn impact(&self, playfield: Vec2, position: Vec2, size: Vec2) -> Option<f32> {
+        swept_circle_rect(self.previous_position, self.position, Self::radius(playfield), position, size)
+    }
+
+    pub fn hits(&self, playfield: Vec2, position: Vec2, size: Vec2) -> bool {
+        self.impact(playfield, position, size).is_some()
+    }
+
+    /// Position and size of the box around everything the bullet touched during the last tick
+    pub fn swept_bounds(&self, playfield: Vec2) -> (Vec2, Vec2) {
+        let radius = Vec2::splat(Self::radius(playfield));
+        let min = self.previous_position.min(self.position) - radius;
+        let max = self.previous_position.max(self.position) + radius;
+        (min, max - min)
     }
 
-    pub fn update(&mut self, delta_time: &f32) {
-        let move_speed = screen_height() / 3.0;
+    pub fn update(&mut self, delta_time: f32, playfield: Vec2) {
+        self.previous_position = self.position;
 
-        self.position.y -= move_speed * *delta_time;
+        let move_speed = match self.owner {
+            Owner::Player => playfield.y / 3.0,
+            Owner::Enemy => playfield.y / 4.0,
+        };
+
+        self.position += self.direction * move_speed * delta_time;
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,104 @@
+use macroquad::prelude::*;
+use crate::collision::swept_circle_rect;
+
+// The classic bunker silhouette, every 'X' is one destructible cell
+const SHAPE: [&str; 8] = [
+    "...XXXXXXXX...",
+    "..XXXXXXXXXX..",
+    ".XXXXXXXXXXXX.",
+    "XXXXXXXXXXXXXX",
+    "XXXXXXXXXXXXXX",
+    "XXXX......XXXX",
+    "XXX........XXX",
+    "XXX........XXX",
+];
+
+/// A shield between the player and the enemies. It is made of a grid of cells which are
+/// eroded by every bullet (no matter who fired it) and by enemies marching through it.
+pub struct Bunker {
+    pub position: Vec2,
+    pub cell_size: f32,
+    cells: Vec<bool>,
+}
+
+impl Bunker {
+    pub const COLUMNS: usize = 14;
+    pub const ROWS: usize = 8;
+
+    pub fn new(position: Vec2, width: f32) -> Self {
+        let cells = SHAPE
+            .iter()
+            .flat_map(|row| row.chars().map(|c| c == 'X'))
+            .collect();
+
+        Self {
+            position,
+            cell_size: width / Self::COLUMNS as f32,
+            cells,
+        }
+    }
+
+    pub fn size(&self) -> Vec2 {
+        vec2(Self::COLUMNS as f32, Self::ROWS as f32) * self.cell_size
+    }
+
+    /// Positions of all cells which are still intact
+    pub fn cells(&self) -> impl Iterator<Item = Vec2> + '_ {
+        self.cells.iter().enumerate().filter(|(_, intact)| **intact).map(|(index, _)| self.cell_position(index))
+    }
+
+    fn cell_position(&self, index: usize) -> Vec2 {
+        let col = index % Self::COLUMNS;
+        let row = index / Self::COLUMNS;
+        self.position + vec2(col as f32, row as f32) * self.cell_size
+    }
+
+    fn destroy(&mut self, col: usize, row: usize) {
+        if col < Self::COLUMNS && row < Self::ROWS {
+            self.cells[row * Self::COLUMNS + col] = false;
+        }
+    }
+
+    /// Checks if a circle moving from `from` to `to` touches an intact cell. If so, the first
+    /// cell it touches and its direct neighbours are destroyed and `true` is returned.
+    pub fn hit(&mut self, from: Vec2, to: Vec2, radius: f32) -> bool {
+        if swept_circle_rect(from, to, radius, self.position, self.size()).is_none() {
+            return false;
+        }
+
+        let cell_size = Vec2::splat(self.cell_size);
+        let first_hit = (0..self.cells.len())
+            .filter(|&index| self.cells[index])
+            .filter_map(|index| {
+                swept_circle_rect(from, to, radius, self.cell_position(index), cell_size).map(|t| (t, index))
+            })
+            .min_by(|a, b| a.0.total_cmp(&b.0));
+        let Some((_, index)) = first_hit else {
+            return false;
+        };
+        let (col, row) = (index % Self::COLUMNS, index / Self::COLUMNS);
+
+        self.destroy(col, row);
+        self.destroy(col + 1, row);
+        self.destroy(col, row + 1);
+        if let Some(left) = col.checked_sub(1) {
+            self.destroy(left, row);
+        }
+        if let Some(up) = row.checked_sub(1) {
+            self.destroy(col, up);
+        }
+        true
+    }
+
+    /// Destroys every cell overlapping the given rectangle
+    pub fn erode(&mut self, position: Vec2, size: Vec2) {
+        let first = ((position - self.position) / self.cell_size).floor().max(Vec2::ZERO);
+        let last = ((position + size - self.position) / self.cell_size).ceil();
+
+        for row in first.y as usize..(last.y.max(0.0) as usize).min(Self::ROWS) {
+            for col in first.x as usize..(last.x.max(0.0) as usize).min(Self::COLUMNS) {
+                self.destroy(col, row);
+            }
+        }
+    }
+}
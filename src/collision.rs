@@ -0,0 +1,131 @@
+use macroquad::prelude::*;
+
+/// Where on its way from `from` to `to` a circle first touches the rectangle, as a fraction
+/// of the way between 0 and 1, or `None` if it misses it. A circle which already overlaps
+/// the rectangle at `from` touches it at 0.
+///
+/// The area in which the center of the circle touches the rectangle is the rectangle grown
+/// by the radius with rounded corners. It is tested as the union of the rectangle grown
+/// horizontally, the rectangle grown vertically and a circle around every corner.
+pub fn swept_circle_rect(from: Vec2, to: Vec2, radius: f32, position: Vec2, size: Vec2) -> Option<f32> {
+    let min = position;
+    let max = position + size;
+    let horizontal = vec2(radius, 0.0);
+    let vertical = vec2(0.0, radius);
+
+    [
+        segment_rect(from, to, min - horizontal, max + horizontal),
+        segment_rect(from, to, min - vertical, max + vertical),
+        segment_circle(from, to, min, radius),
+        segment_circle(from, to, vec2(max.x, min.y), radius),
+        segment_circle(from, to, vec2(min.x, max.y), radius),
+        segment_circle(from, to, max, radius),
+    ]
+    .into_iter()
+    .flatten()
+    .min_by(f32::total_cmp)
+}
+
+/// Slab test of the segment against an axis aligned rectangle
+fn segment_rect(from: Vec2, to: Vec2, min: Vec2, max: Vec2) -> Option<f32> {
+    let direction = to - from;
+    let mut enter: f32 = 0.0;
+    let mut exit: f32 = 1.0;
+
+    for axis in 0..2 {
+        if direction[axis] == 0.0 {
+            if from[axis] < min[axis] || from[axis] > max[axis] {
+                return None;
+            }
+            continue;
+        }
+
+        let first = (min[axis] - from[axis]) / direction[axis];
+        let second = (max[axis] - from[axis]) / direction[axis];
+        enter = enter.max(first.min(second));
+        exit = exit.min(first.max(second));
+        if enter > exit {
+            return None;
+        }
+    }
+
+    Some(enter)
+}
+
+fn segment_circle(from: Vec2, to: Vec2, center: Vec2, radius: f32) -> Option<f32> {
+    let offset = from - center;
+    let c = offset.length_squared() - radius * radius;
+    if c <= 0.0 {
+        return Some(0.0);
+    }
+
+    let direction = to - from;
+    let a = direction.length_squared();
+    if a == 0.0 {
+        return None;
+    }
+    let b = 2.0 * offset.dot(direction);
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+
+    let t = (-b - discriminant.sqrt()) / (2.0 * a);
+    (0.0..=1.0).contains(&t).then_some(t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POSITION: Vec2 = Vec2::new(100.0, 100.0);
+    const SIZE: Vec2 = Vec2::new(50.0, 50.0);
+    const RADIUS: f32 = 5.0;
+
+    /// A move which passes the top left corner of the rectangle `distance` away from it
+    fn past_corner(distance: f32) -> Option<f32> {
+        let offset = distance * std::f32::consts::SQRT_2;
+        let from = vec2(80.0, 120.0 - offset);
+        let to = vec2(120.0 - offset, 80.0);
+        swept_circle_rect(from, to, RADIUS, POSITION, SIZE)
+    }
+
+    #[test]
+    fn fast_circles_dont_tunnel_through_thin_rectangles() {
+        // Thinner than the circle moves in one step
+        let size = vec2(4.0, 100.0);
+        let t = swept_circle_rect(vec2(0.0, 150.0), vec2(300.0, 150.0), RADIUS, POSITION, size);
+        let t = t.expect("the circle passes through the rectangle");
+        assert!((t - 95.0 / 300.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn corners_are_rounded_by_the_radius() {
+        // Heading straight at the corner touches it `RADIUS` before it, not at the grown square corner
+        let t = swept_circle_rect(vec2(80.0, 80.0), vec2(100.0, 100.0), RADIUS, POSITION, SIZE);
+        let length = vec2(20.0, 20.0).length();
+        assert!((t.expect("the circle hits the corner") - (length - RADIUS) / length).abs() < 1e-5);
+
+        assert!(past_corner(RADIUS - 0.1).is_some());
+        assert_eq!(past_corner(RADIUS + 0.1), None);
+    }
+
+    #[test]
+    fn overlapping_circles_touch_at_the_start() {
+        let t = swept_circle_rect(vec2(125.0, 125.0), vec2(300.0, 125.0), RADIUS, POSITION, SIZE);
+        assert_eq!(t, Some(0.0));
+
+        // Only the circle overlaps, its center is still outside
+        let t = swept_circle_rect(vec2(97.0, 125.0), vec2(0.0, 125.0), RADIUS, POSITION, SIZE);
+        assert_eq!(t, Some(0.0));
+    }
+
+    #[test]
+    fn circles_which_dont_move_only_touch_where_they_are() {
+        let outside = vec2(90.0, 90.0);
+        assert_eq!(swept_circle_rect(outside, outside, RADIUS, POSITION, SIZE), None);
+
+        let inside = vec2(120.0, 120.0);
+        assert_eq!(swept_circle_rect(inside, inside, RADIUS, POSITION, SIZE), Some(0.0));
+    }
+}
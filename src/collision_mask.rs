@@ -0,0 +1,72 @@
+use macroquad::prelude::*;
+use crate::collision::swept_circle_rect;
+
+/// Pixels with less alpha than this don't count as part of the sprite
+const ALPHA_THRESHOLD: f32 = 0.5;
+
+/// Which pixels of a texture are opaque, so bullets only hit the visible part of a sprite
+/// instead of the transparent corners of its rectangle. Generated once per loaded texture.
+pub struct CollisionMask {
+    width: usize,
+    height: usize,
+    opaque: Vec<bool>,
+}
+
+impl CollisionMask {
+    pub fn from_image(image: &Image) -> Self {
+        let width = image.width();
+        let height = image.height();
+        let opaque = image.get_image_data().iter().map(|pixel| pixel[3] as f32 / 255.0 >= ALPHA_THRESHOLD).collect();
+
+        Self { width, height, opaque }
+    }
+
+    fn size(&self) -> Vec2 {
+        vec2(self.width as f32, self.height as f32)
+    }
+
+    /// Checks if a circle around `center` touches an opaque pixel, both measured in pixels
+    fn touches(&self, center: Vec2, radius: f32) -> bool {
+        let first_x = (center.x - radius).floor().max(0.0) as usize;
+        let first_y = (center.y - radius).floor().max(0.0) as usize;
+        let last_x = ((center.x + radius).floor().max(0.0) as usize).min(self.width.saturating_sub(1));
+        let last_y = ((center.y + radius).floor().max(0.0) as usize).min(self.height.saturating_sub(1));
+
+        for y in first_y..=last_y {
+            for x in first_x..=last_x {
+                if !self.opaque[y * self.width + x] {
+                    continue;
+                }
+                let pixel = vec2(x as f32, y as f32);
+                let closest = center.clamp(pixel, pixel + Vec2::ONE);
+                if closest.distance_squared(center) <= radius * radius {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// Like `swept_circle_rect`, but for the sprite drawn with this mask at `position` with
+    /// `size`. The way of the circle is sampled in steps of its radius, so no pixel between
+    /// two samples is skipped.
+    pub fn swept_circle_hit(&self, from: Vec2, to: Vec2, radius: f32, position: Vec2, size: Vec2) -> Option<f32> {
+        if self.opaque.is_empty() {
+            return None;
+        }
+
+        // Transform everything into pixels of the texture
+        let scale = self.size() / size;
+        let from = (from - position) * scale;
+        let to = (to - position) * scale;
+        let radius = radius * scale.max_element();
+
+        let enter = swept_circle_rect(from, to, radius, Vec2::ZERO, self.size())?;
+        let distance = from.distance(to) * (1.0 - enter);
+        let steps = (distance / radius.max(0.5)).ceil().max(1.0) as usize;
+
+        (0..=steps)
+            .map(|step| enter + (1.0 - enter) * step as f32 / steps as f32)
+            .find(|&t| self.touches(from.lerp(to, t), radius))
+    }
+}
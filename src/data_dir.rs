@@ -0,0 +1,25 @@
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// Environment variable to store the game data somewhere else
+pub const ENVIRONMENT_VARIABLE: &str = "CODEINVADERS_DATA";
+const APPLICATION_DIRECTORY: &str = "codeinvaders";
+
+/// The directory for data which has to survive between runs, like the high scores.
+///
+/// In order of precedence this is the `--data-dir` option, the `CODEINVADERS_DATA` environment
+/// variable or the data directory of the platform, which follows the XDG base directories on
+/// Linux (`$XDG_DATA_HOME/codeinvaders`, usually `~/.local/share/codeinvaders`). If no home
+/// directory can be found the working directory is used.
+pub fn resolve(override_directory: Option<&Path>) -> PathBuf {
+    if let Some(directory) = override_directory {
+        return directory.to_path_buf();
+    }
+    if let Some(directory) = env::var_os(ENVIRONMENT_VARIABLE).filter(|directory| !directory.is_empty()) {
+        return PathBuf::from(directory);
+    }
+
+    dirs::data_dir()
+        .map(|directory| directory.join(APPLICATION_DIRECTORY))
+        .unwrap_or_else(|| PathBuf::from("."))
+}
@@ -0,0 +1,187 @@
+use macroquad::prelude::*;
+use serde::Deserialize;
+
+/// How far below the playfield a diving enemy flies before it wraps around to the top,
+/// as a fraction of the playfield height
+const EXIT_MARGIN: f32 = 0.1;
+
+/// The attack patterns a level can let its enemies fly, listed in `dive_patterns` of a level file.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DivePattern {
+    /// Curves out to the side and down to the player, then flies back up to its slot
+    Swoop,
+    /// Flies a loop next to the formation before diving at the player and returning
+    Loop,
+    /// Snakes down the screen around the player and leaves at the bottom
+    Zigzag,
+    /// Dives almost straight through the player and leaves at the bottom
+    Kamikaze,
+}
+
+/// What happens at the end of a dive path
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum DiveEnd {
+    /// Fly back to the slot from where the path ended
+    Return,
+    /// Leave the screen at the bottom and fly back to the slot from above
+    Exit,
+}
+
+/// A cubic Bezier curve given by its start, two control points and its end
+type Curve = [Vec2; 4];
+
+fn point_on_curve(curve: &Curve, t: f32) -> Vec2 {
+    let u = 1.0 - t;
+    curve[0] * (u * u * u) + curve[1] * (3.0 * u * u * t) + curve[2] * (3.0 * u * t * t) + curve[3] * (t * t * t)
+}
+
+/// Approximates the length of the curve by averaging the chord and the control polygon
+fn curve_length(curve: &Curve) -> f32 {
+    let chord = curve[0].distance(curve[3]);
+    let polygon = curve[0].distance(curve[1]) + curve[1].distance(curve[2]) + curve[2].distance(curve[3]);
+    (chord + polygon) / 2.0
+}
+
+impl DivePattern {
+    /// The curves of the dive from `start` towards `target`. `side` is `1.0` or `-1.0` and
+    /// mirrors the pattern horizontally.
+    fn path(&self, start: Vec2, target: Vec2, side: f32, playfield: Vec2) -> (Vec<Curve>, DiveEnd) {
+        let width = playfield.x;
+        let height = playfield.y;
+        let bottom = height * (1.0 + EXIT_MARGIN);
+
+        match self {
+            DivePattern::Swoop => (
+                vec![[
+                    start,
+                    start + vec2(side * width * 0.15, -height * 0.1),
+                    target + vec2(-side * width * 0.2, -height * 0.3),
+                    target,
+                ]],
+                DiveEnd::Return,
+            ),
+            DivePattern::Loop => {
+                let loop_end = start + vec2(side * width * 0.1, height * 0.15);
+                (
+                    vec![
+                        [
+                            start,
+                            start + vec2(side * width * 0.2, -height * 0.2),
+                            start + vec2(side * width * 0.3, height * 0.15),
+                            loop_end,
+                        ],
+                        [
+                            loop_end,
+                            loop_end + vec2(-side * width * 0.1, 0.0),
+                            target + vec2(0.0, -height * 0.3),
+                            target,
+                        ],
+                    ],
+                    DiveEnd::Return,
+                )
+            }
+            DivePattern::Zigzag => {
+                let turns = 3;
+                let mut curves = Vec::with_capacity(turns);
+                let mut from = start;
+                for turn in 1..=turns {
+                    let offset = if turn % 2 == 1 { side } else { -side };
+                    let to = vec2(
+                        target.x + offset * width * 0.15,
+                        start.y + (bottom - start.y) * turn as f32 / turns as f32,
+                    );
+                    let half_drop = vec2(0.0, (to.y - from.y) / 2.0);
+                    curves.push([from, from + half_drop, to - half_drop, to]);
+                    from = to;
+                }
+                (curves, DiveEnd::Exit)
+            }
+            DivePattern::Kamikaze => (
+                vec![[
+                    start,
+                    start + vec2(side * width * 0.05, -height * 0.1),
+                    target + vec2(0.0, -height * 0.2),
+                    vec2(target.x, bottom),
+                ]],
+                DiveEnd::Exit,
+            ),
+        }
+    }
+}
+
+/// The state of an enemy which has broken out of the formation and follows a dive path.
+pub struct Dive {
+    pub position: Vec2,
+    pub previous_position: Vec2,
+    curves: Vec<Curve>,
+    curve: usize,
+    /// Progress on the current curve from 0 to 1
+    t: f32,
+    end: DiveEnd,
+    side: f32,
+    /// Units per second along the path
+    speed: f32,
+    /// Where the flight back to the slot started, once the path is finished
+    return_from: Option<Vec2>,
+}
+
+impl Dive {
+    pub fn new(pattern: DivePattern, start: Vec2, target: Vec2, side: f32, playfield: Vec2) -> Self {
+        let (curves, end) = pattern.path(start, target, side, playfield);
+
+        Self {
+            position: start,
+            previous_position: start,
+            curves,
+            curve: 0,
+            t: 0.0,
+            end,
+            side,
+            speed: playfield.y / 2.5,
+            return_from: None,
+        }
+    }
+
+    /// The curve back to the slot, which moves with the formation while the enemy flies
+    fn return_curve(&self, from: Vec2, slot_position: Vec2, playfield: Vec2) -> Curve {
+        [
+            from,
+            from.lerp(slot_position, 1.0 / 3.0) + vec2(self.side * playfield.x * 0.1, 0.0),
+            slot_position - vec2(0.0, playfield.y * 0.1),
+            slot_position,
+        ]
+    }
+
+    /// Moves along the path and returns `true` once the enemy is back in its slot
+    pub fn update(&mut self, delta_time: f32, slot_position: Vec2, playfield: Vec2) -> bool {
+        self.previous_position = self.position;
+
+        let curve = match self.return_from {
+            Some(from) => self.return_curve(from, slot_position, playfield),
+            None => self.curves[self.curve],
+        };
+        self.t += self.speed * delta_time / curve_length(&curve).max(1.0);
+        if self.t < 1.0 {
+            self.position = point_on_curve(&curve, self.t);
+            return false;
+        }
+
+        self.t = 0.0;
+        self.position = curve[3];
+        if self.return_from.is_some() {
+            return true;
+        }
+
+        self.curve += 1;
+        if self.curve == self.curves.len() {
+            if self.end == DiveEnd::Exit {
+                // Wrap around to the top without interpolating across the whole screen
+                self.position = vec2(slot_position.x, -playfield.y * EXIT_MARGIN);
+                self.previous_position = self.position;
+            }
+            self.return_from = Some(self.position);
+        }
+        false
+    }
+}
@@ -1,65 +1,130 @@
-use macroquad::prelude::*;
 use std::rc::Rc;
+use macroquad::prelude::*;
+use serde::Deserialize;
+use crate::dive::Dive;
+use crate::formation::Formation;
+use crate::pool::Handle;
+
+/// How an enemy shoots when it is picked as the next shooter
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum FireBehaviour {
+    /// Never shoots
+    None,
+    /// Shoots straight down
+    #[default]
+    Straight,
+    /// Shoots towards the player
+    Aimed,
+}
+
+fn default_hit_points() -> u32 { 1 }
+fn default_score() -> i32 { 10 }
+fn default_speed_modifier() -> f32 { 1.0 }
+fn default_pixel_collision() -> bool { true }
 
-pub enum Direction {
-    Left,
-    Right,
+/// The properties shared by all enemies of one type, defined in the `enemy_types` of a level file.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct EnemyKind {
+    pub texture: String,
+    #[serde(default = "default_hit_points")]
+    pub hit_points: u32,
+    /// Points for destroying the enemy
+    #[serde(default = "default_score")]
+    pub score: i32,
+    /// Multiplied with the speed of the level, the formation uses the average of all remaining enemies
+    #[serde(default = "default_speed_modifier")]
+    pub speed_modifier: f32,
+    #[serde(default)]
+    pub fire: FireBehaviour,
+    /// Only the opaque pixels of the texture can be hit instead of its whole rectangle
+    #[serde(default = "default_pixel_collision")]
+    pub pixel_collision: bool,
+}
+
+impl EnemyKind {
+    /// A plain enemy with one hit point which only differs by its texture
+    pub fn with_texture(texture: &str) -> Self {
+        Self {
+            texture: texture.to_string(),
+            hit_points: default_hit_points(),
+            score: default_score(),
+            speed_modifier: default_speed_modifier(),
+            fire: FireBehaviour::default(),
+            pixel_collision: default_pixel_collision(),
+        }
+    }
 }
 
+/// An enemy in the formation. It only knows its slot in the grid, the position follows
+/// from where the formation currently is unless the enemy is on a dive.
 pub struct Enemy {
-    pub texture: Rc<Texture2D>,
+    pub kind: Rc<EnemyKind>,
+    pub texture_size: Vec2,
     pub scale: f32,
-    pub position: Vec2,
-    pub level: i8,
+    /// Column and row in the formation
+    pub slot: (usize, usize),
+    pub hit_points: u32,
+    /// Seconds the enemy still flashes after a hit which didn't destroy it
+    pub hit_flash: f32,
     pub collided: bool,
-    current_direction: Direction,
+    /// The piercing bullets which already hit the enemy and are still flying
+    pub pierced_by: Vec<Handle>,
+    /// Set while the enemy has left its slot to attack the player
+    pub dive: Option<Dive>,
 }
 
 impl Enemy {
-    pub fn new(texture: Rc<Texture2D>, scale: f32, level: i8, position: Vec2) -> Self {
+    pub const HIT_FLASH_DURATION: f32 = 0.15;
+
+    pub fn new(kind: Rc<EnemyKind>, texture_size: Vec2, scale: f32, slot: (usize, usize)) -> Self {
         Self {
-            texture,
+            hit_points: kind.hit_points,
+            kind,
+            texture_size,
             scale,
-            position,
-            level,
+            slot,
+            hit_flash: 0.0,
             collided: false,
-            current_direction: Direction::Left,
+            pierced_by: Vec::new(),
+            dive: None,
         }
     }
 
-    pub fn draw(&self) {
-        draw_texture_ex(
-            &self.texture,
-            self.position.x,
-            self.position.y,
-            WHITE,
-            DrawTextureParams {
-                dest_size: Some(Vec2::new(self.texture.width() * self.scale, self.texture.height() * self.scale)),
-                ..Default::default()
-            },
-        );
+    pub fn size(&self) -> Vec2 {
+        self.texture_size * self.scale
     }
 
-    fn change_direction(&mut self) {
-        self.position.y += self.texture.height() * self.scale;
-
-        self.current_direction = match self.current_direction {
-            Direction::Left => Direction::Right,
-            Direction::Right => Direction::Left,
-        };
+    pub fn position(&self, formation: &Formation) -> Vec2 {
+        match &self.dive {
+            Some(dive) => dive.position,
+            None => formation.slot_position(self.slot),
+        }
     }
 
-    pub fn update(&mut self, direction_change: &bool, delta_time: &f32) {
-        let base_speed: f32 = screen_width() / 25.0;
-        let move_speed: f32 = base_speed * (1.5_f32).powi(self.level as i32);
+    pub fn is_diving(&self) -> bool {
+        self.dive.is_some()
+    }
 
-        if *direction_change {
-            self.change_direction();
+    /// Takes one hit point and returns `true` if this destroyed the enemy
+    pub fn hit(&mut self) -> bool {
+        self.hit_points = self.hit_points.saturating_sub(1);
+        if self.hit_points == 0 {
+            self.collided = true;
+        } else {
+            self.hit_flash = Self::HIT_FLASH_DURATION;
         }
+        self.collided
+    }
+
+    pub fn update(&mut self, delta_time: f32, formation: &Formation, playfield: Vec2) {
+        self.hit_flash = (self.hit_flash - delta_time).max(0.0);
 
-        match self.current_direction {
-            Direction::Left => self.position.x += move_speed * *delta_time,
-            Direction::Right => self.position.x -= move_speed * *delta_time,
-        };
+        if let Some(dive) = self.dive.as_mut() {
+            if dive.update(delta_time, formation.slot_position(self.slot), playfield) {
+                self.dive = None;
+            }
+        }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,26 @@
+use macroquad::prelude::*;
+
+/// A short text, like the points of a hit, which slowly rises and disappears.
+pub struct FloatingText {
+    pub text: String,
+    pub position: Vec2,
+    /// Seconds until the text disappears
+    pub remaining: f32,
+}
+
+impl FloatingText {
+    pub const DURATION: f32 = 1.5;
+
+    pub fn new(text: String, position: Vec2) -> Self {
+        Self {
+            text,
+            position,
+            remaining: Self::DURATION,
+        }
+    }
+
+    pub fn update(&mut self, delta_time: f32) {
+        self.position.y -= 40.0 * delta_time;
+        self.remaining -= delta_time;
+    }
+}
@@ -0,0 +1,91 @@
+use macroquad::prelude::*;
+use crate::enemy::Enemy;
+
+pub enum Direction {
+    Left,
+    Right,
+}
+
+/// Moves the grid of enemies as one unit in discrete steps, like the arcade original: every
+/// step the whole formation moves sideways, when it would leave the playfield it drops by
+/// exactly one row instead and turns around. The fewer enemies are left, the faster it marches.
+pub struct Formation {
+    /// Position of the top left slot
+    pub origin: Vec2,
+    /// Distance between two columns and two rows
+    pub spacing: Vec2,
+    pub enemy_width: f32,
+    direction: Direction,
+    step_size: f32,
+    /// Seconds between two steps while the formation is complete
+    step_interval: f32,
+    step_timer: f32,
+    initial_count: usize,
+}
+
+impl Formation {
+    /// `speed` is the average horizontal speed in units per second while the formation is complete
+    pub fn new(origin: Vec2, spacing: Vec2, enemy_width: f32, speed: f32, initial_count: usize, playfield: Vec2) -> Self {
+        let step_size = playfield.x / 160.0;
+        let step_interval = step_size / speed;
+
+        Self {
+            origin,
+            spacing,
+            enemy_width,
+            direction: Direction::Right,
+            step_size,
+            step_interval,
+            step_timer: step_interval,
+            initial_count: initial_count.max(1),
+        }
+    }
+
+    pub fn slot_position(&self, slot: (usize, usize)) -> Vec2 {
+        self.origin + vec2(slot.0 as f32, slot.1 as f32) * self.spacing
+    }
+
+    /// Seconds until the next step. Shrinks with the number of enemies left, down to a fifth
+    /// for the last one, and with the average speed modifier of their kinds.
+    fn current_step_interval(&self, enemies: &[Enemy]) -> f32 {
+        let remaining = enemies.len() as f32 / self.initial_count as f32;
+        let speed_modifier = enemies.iter().map(|enemy| enemy.kind.speed_modifier).sum::<f32>() / enemies.len() as f32;
+
+        self.step_interval * (0.2 + 0.8 * remaining) / speed_modifier
+    }
+
+    pub fn update(&mut self, enemies: &[Enemy], delta_time: f32, left_bound: f32, right_bound: f32) {
+        if enemies.is_empty() {
+            return;
+        }
+
+        self.step_timer -= delta_time;
+        if self.step_timer > 0.0 {
+            return;
+        }
+        self.step_timer += self.current_step_interval(enemies);
+
+        let first_column = enemies.iter().map(|enemy| enemy.slot.0).min().unwrap_or(0);
+        let last_column = enemies.iter().map(|enemy| enemy.slot.0).max().unwrap_or(0);
+        let left = self.slot_position((first_column, 0)).x;
+        let right = self.slot_position((last_column, 0)).x + self.enemy_width;
+
+        let hit_wall = match self.direction {
+            Direction::Left => left - self.step_size < left_bound,
+            Direction::Right => right + self.step_size > right_bound,
+        };
+
+        if hit_wall {
+            self.origin.y += self.spacing.y;
+            self.direction = match self.direction {
+                Direction::Left => Direction::Right,
+                Direction::Right => Direction::Left,
+            };
+        } else {
+            match self.direction {
+                Direction::Left => self.origin.x -= self.step_size,
+                Direction::Right => self.origin.x += self.step_size,
+            }
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+
+/// The rules a game is played with. Every mode has its own leaderboards.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GameMode {
+    /// Level after level until all lives are lost
+    #[default]
+    Classic,
+}
+
+impl GameMode {
+    pub fn label(&self) -> &'static str {
+        match self {
+            GameMode::Classic => "CLASSIC",
+        }
+    }
+}
+
+/// How hard the enemies are, chosen in the main menu
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+    /// Multiplies how fast the enemies march, how often they shoot and how often they dive
+    pub fn enemy_multiplier(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.75,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.35,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "EASY",
+            Difficulty::Normal => "NORMAL",
+            Difficulty::Hard => "HARD",
+        }
+    }
+
+    /// The next harder difficulty, the hardest one stays
+    pub fn harder(self) -> Self {
+        Self::from_index(self.index() + 1).unwrap_or(self)
+    }
+
+    /// The next easier difficulty, the easiest one stays
+    pub fn easier(self) -> Self {
+        self.index().checked_sub(1).and_then(Self::from_index).unwrap_or(self)
+    }
+
+    /// Position in `ALL`, also used to store the difficulty in replays
+    pub fn index(self) -> u8 {
+        self as u8
+    }
+
+    pub fn from_index(index: u8) -> Option<Self> {
+        Self::ALL.get(index as usize).copied()
+    }
+}
@@ -1,65 +1,719 @@
-use std::fs::File;
+use std::cmp::Reverse;
+use std::fs::{self, File};
 use std::io::{self, Read, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use hmac::{Hmac, Mac};
 use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use crate::game_mode::{Difficulty, GameMode};
 
+type HmacSha256 = Hmac<Sha256>;
 
+/// Key of the entry signatures. The default only makes editing the file by hand obvious,
+/// builds for a shared leaderboard should bake in their own secret by setting the
+/// `CODEINVADERS_SCORE_KEY` environment variable at compile time.
+const SIGNING_KEY: &[u8] = match option_env!("CODEINVADERS_SCORE_KEY") {
+    Some(key) => key.as_bytes(),
+    None => b"CodeInvaders high scores",
+};
+
+/// Level directory of the versions before there were level sets
+const LEGACY_LEVEL_SET: &str = "levels";
+/// How long to wait for the leaderboard server before using the local high scores
+const SERVER_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Statistics of the game a high score was reached in. Entries of files written before the
+/// statistics existed get the default, which has an empty `version`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct GameStats {
+    /// Highest level reached
+    pub level: i8,
+    pub shots: u32,
+    pub hits: u32,
+    /// Share of the shots which hit something, between 0 and 1
+    pub accuracy: f32,
+    /// Seconds spent playing the levels
+    pub play_time: f32,
+    /// Seconds since the Unix epoch when the game ended
+    pub date: u64,
+    /// Version of the game which recorded the entry
+    pub version: String,
+}
+
+impl GameStats {
+    pub fn new(level: i8, shots: u32, hits: u32, play_time: f32) -> Self {
+        Self {
+            level,
+            shots,
+            hits,
+            accuracy: if shots == 0 { 0.0 } else { hits as f32 / shots as f32 },
+            play_time,
+            date: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_secs()),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+
+    /// One line per statistic for the details view
+    pub fn details(&self) -> Vec<String> {
+        if self.version.is_empty() {
+            return vec!["No statistics recorded for this entry".to_string()];
+        }
+
+        let play_time = self.play_time.round() as u32;
+        vec![
+            format!("LEVEL {}   SHOTS {}   HITS {}   ACCURACY {:.0}%", self.level, self.shots, self.hits, self.accuracy * 100.0),
+            format!("PLAY TIME {}:{:02}   DATE {}   VERSION {}", play_time / 60, play_time % 60, format_date(self.date), self.version),
+        ]
+    }
+}
+
+/// Formats seconds since the Unix epoch as `YYYY-MM-DD` (UTC)
+fn format_date(unix_seconds: u64) -> String {
+    // Converts days to a civil date, see http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let days = (unix_seconds / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Signature {
+    Valid,
+    /// Entries written before the signatures existed
+    Missing,
+    Invalid,
+}
+
+/// Which leaderboard a score belongs to, only games with the same key compete
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LeaderboardKey {
+    pub mode: GameMode,
+    pub difficulty: Difficulty,
+    /// Name of the directory the levels were loaded from
+    pub level_set: String,
+}
+
+impl LeaderboardKey {
+    /// The board of the scores saved before there were several boards, which were all
+    /// classic games on normal with the default levels
+    fn legacy() -> Self {
+        LeaderboardKey { mode: GameMode::Classic, difficulty: Difficulty::Normal, level_set: LEGACY_LEVEL_SET.to_string() }
+    }
+
+    pub fn title(&self) -> String {
+        format!("{} - {} - {}", self.mode.label(), self.difficulty.label(), self.level_set.to_uppercase())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HighScoreEntry {
+    pub name: String,
+    pub score: i32,
+    #[serde(default)]
+    stats: GameStats,
+    /// SHA-256 of the replay of the game, see `Replay::hash`
+    #[serde(default)]
+    replay_hash: String,
+    /// HMAC-SHA256 over the name, score, replay hash, leaderboard and statistics as hex
+    #[serde(default)]
+    signature: String,
+}
+
+impl HighScoreEntry {
+    fn new(name: String, score: i32, stats: GameStats, replay_hash: String, key: &LeaderboardKey) -> Self {
+        let mut entry = HighScoreEntry { name, score, stats, replay_hash, signature: String::new() };
+        entry.signature = hex::encode(entry.mac(key).finalize().into_bytes());
+        entry
+    }
+
+    fn mac(&self, key: &LeaderboardKey) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(SIGNING_KEY).expect("HMAC takes keys of any length");
+        // The length keeps the end of the name from being taken for the score
+        mac.update(&(self.name.len() as u64).to_le_bytes());
+        mac.update(self.name.as_bytes());
+        mac.update(&self.score.to_le_bytes());
+        mac.update(self.replay_hash.as_bytes());
+        // Serializing a struct can't fail, and the statistics serialize the same after being
+        // read back, so they can be checked in the form they were saved
+        mac.update(serde_json::to_string(key).unwrap().as_bytes());
+        mac.update(serde_json::to_string(&self.stats).unwrap().as_bytes());
+        mac
+    }
+
+    fn check_signature(&self, key: &LeaderboardKey) -> Signature {
+        if self.signature.is_empty() {
+            return Signature::Missing;
+        }
+        match hex::decode(&self.signature) {
+            Ok(signature) if self.mac(key).verify_slice(&signature).is_ok() => Signature::Valid,
+            _ => Signature::Invalid,
+        }
+    }
+}
+
+/// A new entry sent to the leaderboard server
 #[derive(Serialize, Deserialize, Debug)]
-struct HighScoreEntry {
-    name: String,
-    score: i32,
+pub struct Submission {
+    pub key: LeaderboardKey,
+    pub entry: HighScoreEntry,
+}
+
+/// Where the shown high scores come from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ServerStatus {
+    /// No leaderboard server is used
+    Offline,
+    /// A request to the server is running, the boards shown may be outdated
+    Syncing,
+    /// The boards of the server are shown
+    Connected,
+    /// The last request to the server failed, the local boards are shown
+    Unreachable,
+}
+
+/// The best scores of all games with the same key, sorted by score
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Leaderboard {
+    pub key: LeaderboardKey,
+    entries: Vec<HighScoreEntry>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct HighScores {
+    /// Schema of the file, files written before it existed have version 0
+    #[serde(default)]
+    version: u32,
+    /// Sorted by key, so the tabs of the high score screen have a stable order
+    #[serde(default)]
+    boards: Vec<Leaderboard>,
+    /// The single list of the versions before there were leaderboards, moved to the legacy
+    /// board when loading
+    #[serde(default, skip_serializing)]
     entries: Vec<HighScoreEntry>,
+    #[serde(skip)]
+    table_length: usize,
+    #[serde(skip)]
+    path: PathBuf,
+    /// Set when the file was written by a newer version of the game, which must not be
+    /// overwritten with a format it doesn't expect
+    #[serde(skip)]
+    read_only: bool,
+    /// URL of the leaderboard server, if any
+    #[serde(skip)]
+    server: Option<String>,
+    /// The boards of the server, `None` while it can't be reached
+    #[serde(skip)]
+    remote_boards: Option<Vec<Leaderboard>>,
+    /// Answer of the request to the server which is still running, if any
+    #[serde(skip)]
+    pending: Option<Receiver<io::Result<Vec<Leaderboard>>>>,
 }
 
 impl HighScores {
-    const FILE_PATH: &'static str = "high_scores.json";
+    pub const FILE_NAME: &'static str = "high_scores.json";
+    pub const DEFAULT_TABLE_LENGTH: usize = 10;
+    /// Version of the file format written by this version of the game
+    const SCHEMA_VERSION: u32 = 2;
 
-    pub fn new() -> Self {
-        HighScores { entries: Vec::new() }
+    /// High scores stored in `FILE_NAME` inside of `directory`, keeping the best
+    /// `table_length` scores of every leaderboard
+    pub fn new(directory: &Path, table_length: usize) -> Self {
+        HighScores {
+            version: Self::SCHEMA_VERSION,
+            boards: Vec::new(),
+            entries: Vec::new(),
+            table_length: table_length.max(1),
+            path: directory.join(Self::FILE_NAME),
+            read_only: false,
+            server: None,
+            remote_boards: None,
+            pending: None,
+        }
     }
 
+    /// The copy of the previous file, replaced on every save
+    fn backup_path(&self) -> PathBuf {
+        self.path.with_extension("json.bak")
+    }
+
+    /// Loads the high scores and creates the directory on the first run.
+    ///
+    /// Entries with a wrong signature were edited and are removed, entries without one were
+    /// written by older versions and are kept, but marked as unverified.
+    ///
+    /// A file which can't be read is moved aside, so it can be inspected and isn't overwritten
+    /// by the next save, and the backup is loaded instead if possible. A file of a newer
+    /// version of the game is left alone and nothing is saved until the game is restarted.
+    /// The returned error describes what happened, the high scores can be used either way.
     pub fn load(&mut self) -> io::Result<()> {
-        if Path::new(Self::FILE_PATH).exists() {
-            let mut file = File::open(Self::FILE_PATH)?;
-            let mut contents = String::new();
-            file.read_to_string(&mut contents)?;
-            *self = serde_json::from_str(&contents)?;
+        if let Some(directory) = self.path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        if !self.path.exists() {
+            return Ok(());
+        }
+
+        match Self::read(&self.path) {
+            Ok(boards) => {
+                self.boards = boards;
+                self.remove_forged()
+            }
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                let quarantine_path = self.quarantine()?;
+                let recovery = match Self::read(&self.backup_path()) {
+                    Ok(boards) => {
+                        self.boards = boards;
+                        match self.remove_forged() {
+                            Ok(()) => "restored the backup".to_string(),
+                            Err(e) => format!("restored the backup, but {e}"),
+                        }
+                    }
+                    Err(_) => "starting with empty high scores".to_string(),
+                };
+                Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{} can't be read ({}), moved it to {} and {}", self.path.display(), e, quarantine_path.display(), recovery),
+                ))
+            }
+            Err(e) if e.kind() == io::ErrorKind::Unsupported => {
+                self.read_only = true;
+                Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    format!("{} was {}, it is kept and no high scores are saved", self.path.display(), e),
+                ))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Reads the leaderboards of a high score file, a file which isn't valid is `InvalidData`
+    /// and one of a newer version `Unsupported`
+    fn read(path: &Path) -> io::Result<Vec<Leaderboard>> {
+        let mut file = File::open(path)?;
+        let mut contents = String::new();
+        file.read_to_string(&mut contents)?;
+        let loaded: HighScores = serde_json::from_str(&contents)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        if loaded.version > Self::SCHEMA_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("written by a newer version of the game with schema version {}", loaded.version),
+            ));
+        }
+
+        let mut boards = loaded.boards;
+        if !loaded.entries.is_empty() {
+            let key = LeaderboardKey::legacy();
+            boards.push(Leaderboard { key, entries: loaded.entries });
+        }
+        boards.sort_by(|a, b| a.key.cmp(&b.key));
+        Ok(boards)
+    }
+
+    /// Removes the entries whose signature doesn't match
+    fn remove_forged(&mut self) -> io::Result<()> {
+        let mut forged = Vec::new();
+        for board in &mut self.boards {
+            board.entries.retain(|entry| {
+                let valid = entry.check_signature(&board.key) != Signature::Invalid;
+                if !valid {
+                    forged.push(format!("{} - {}", entry.name, entry.score));
+                }
+                valid
+            });
         }
+
+        if forged.is_empty() {
+            return Ok(());
+        }
+        Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("removed {} high score(s) with an invalid signature: {}", forged.len(), forged.join(", ")),
+        ))
+    }
+
+    /// Checks the signatures of the saved entries without changing the file. Prints one line
+    /// per entry and returns `true` if all of them are signed correctly.
+    pub fn verify(&self) -> io::Result<bool> {
+        let boards = Self::read(&self.path)?;
+        let mut all_valid = true;
+        for board in &boards {
+            println!("{}", board.key.title());
+            for (i, entry) in board.entries.iter().enumerate() {
+                let signature = entry.check_signature(&board.key);
+                all_valid &= signature == Signature::Valid;
+                let status = match signature {
+                    Signature::Valid => "ok",
+                    Signature::Missing => "unsigned",
+                    Signature::Invalid => "INVALID SIGNATURE",
+                };
+                println!("  {}: {} - {}  {}", i + 1, entry.name, entry.score, status);
+            }
+        }
+        Ok(all_valid)
+    }
+
+    /// Renames the high score file to `high_scores.json.corrupt-<milliseconds since the epoch>`
+    fn quarantine(&self) -> io::Result<PathBuf> {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_millis());
+        let quarantine_path = self.path.with_extension(format!("json.corrupt-{timestamp}"));
+        fs::rename(&self.path, &quarantine_path)?;
+        Ok(quarantine_path)
+    }
+
+    /// Copies `high_scores.json` from the working directory, where older versions stored the
+    /// high scores. This only happens as long as there are no high scores in the data
+    /// directory yet.
+    pub fn import_legacy(&self) -> io::Result<()> {
+        let legacy_path = Path::new(Self::FILE_NAME);
+        if !legacy_path.exists() || self.path.exists() {
+            return Ok(());
+        }
+
+        if let Some(directory) = self.path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        fs::copy(legacy_path, &self.path)?;
+        println!("Imported the high scores from {} to {}", legacy_path.display(), self.path.display());
         Ok(())
     }
 
+    /// Writes the high scores to a temporary file first and then renames it over the old
+    /// file, so a crash while saving leaves either the old or the new file, never half of one.
     fn save(&self) -> io::Result<()> {
-        let mut file = File::create(Self::FILE_PATH)?;
+        if self.read_only {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("{} belongs to a newer version of the game", self.path.display()),
+            ));
+        }
+        if let Some(directory) = self.path.parent() {
+            fs::create_dir_all(directory)?;
+        }
         let contents = serde_json::to_string(&self)?;
+        let temporary_path = self.path.with_extension("json.tmp");
+        let mut file = File::create(&temporary_path)?;
         file.write_all(contents.as_bytes())?;
+        file.sync_all()?;
+
+        if self.path.exists() {
+            fs::copy(&self.path, self.backup_path())?;
+        }
+        fs::rename(&temporary_path, &self.path)?;
         Ok(())
     }
 
-    pub fn qualifies(&self, score: i32) -> bool {
-        self.entries.len() < 10 || score > self.entries.last().unwrap().score
+    /// The boards of the server while it can be reached, the local ones otherwise
+    fn shown_boards(&self) -> &[Leaderboard] {
+        self.remote_boards.as_deref().unwrap_or(&self.boards)
+    }
+
+    /// Whether the score makes it onto the local board of `key` or onto the one of the
+    /// server, every new entry is saved locally even if the server has better scores
+    pub fn qualifies(&self, key: &LeaderboardKey, score: i32) -> bool {
+        let fits = |boards: &[Leaderboard]| {
+            let board = boards.iter().find(|board| board.key == *key);
+            let entries = board.map_or(&[][..], |board| &board.entries);
+            entries.len() < self.table_length || score > entries[self.table_length - 1].score
+        };
+        fits(&self.boards) || self.remote_boards.as_deref().is_some_and(fits)
     }
 
-    pub fn add_score(&mut self, name: String, score: i32) {
+    /// Adds a signed entry to the leaderboard of `key`, `replay_hash` ties it to the replay
+    /// of the game. With a leaderboard server the entry is submitted there as well, in the
+    /// background.
+    pub fn add_score(&mut self, key: &LeaderboardKey, name: String, score: i32, stats: GameStats, replay_hash: String) {
         let trimmed_name = name.trim().to_string();
+        let entry = HighScoreEntry::new(trimmed_name, score, stats, replay_hash, key);
 
-        self.entries.push(HighScoreEntry { name: trimmed_name, score });
-        self.entries.sort_by(|a, b| b.score.cmp(&a.score));
-        self.entries.truncate(10);
+        if self.server.is_some() {
+            self.request(Some(Submission { key: key.clone(), entry: entry.clone() }));
+        }
 
-        if let Err(e) = self.save() {
+        if let Err(e) = self.insert(key, entry) {
             eprintln!("Error saving high scores: {}", e);
         }
     }
 
-    pub fn display(&self) -> Vec<String> {
+    /// Adds an entry which was signed somewhere else, used by the leaderboard server.
+    /// Entries without a valid signature are rejected as `InvalidData`, entries of a game
+    /// which is already on the board as `AlreadyExists`.
+    pub fn add_signed(&mut self, key: &LeaderboardKey, entry: HighScoreEntry) -> io::Result<()> {
+        if entry.check_signature(key) != Signature::Valid {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "the signature of the entry is invalid"));
+        }
+        let board = self.boards.iter().find(|board| board.key == *key);
+        if board.is_some_and(|board| board.entries.iter().any(|existing| existing.replay_hash == entry.replay_hash)) {
+            return Err(io::Error::new(io::ErrorKind::AlreadyExists, "the game of the entry is already on the board"));
+        }
+        self.insert(key, entry)
+    }
+
+    /// Sorts the entry into the local board of `key` and saves the high scores
+    fn insert(&mut self, key: &LeaderboardKey, entry: HighScoreEntry) -> io::Result<()> {
+        let index = match self.boards.binary_search_by(|board| board.key.cmp(key)) {
+            Ok(index) => index,
+            Err(index) => {
+                self.boards.insert(index, Leaderboard { key: key.clone(), entries: Vec::new() });
+                index
+            }
+        };
+        let board = &mut self.boards[index];
+        board.entries.push(entry);
+        board.entries.sort_by_key(|entry| Reverse(entry.score));
+        board.entries.truncate(self.table_length);
+
+        self.save()
+    }
+
+    /// The best `limit` entries of every local board, what the leaderboard server sends
+    pub fn top(&self, limit: usize) -> Vec<Leaderboard> {
+        self.boards
+            .iter()
+            .map(|board| Leaderboard { key: board.key.clone(), entries: board.entries.iter().take(limit).cloned().collect() })
+            .collect()
+    }
+
+    /// Shows the boards of the leaderboard server at `url` instead of the local ones once they
+    /// are fetched. The scores are still saved locally as well, so nothing is lost while the
+    /// server is down.
+    pub fn connect(&mut self, url: &str) {
+        self.server = Some(url.trim_end_matches('/').to_string());
+        self.request(None);
+    }
+
+    /// Submits the entry, if any, and fetches the boards of the server on a thread of its own,
+    /// so a slow server doesn't freeze the game. `poll` picks up the answer.
+    fn request(&mut self, submission: Option<Submission>) {
+        let Some(server) = self.server.clone() else {
+            return;
+        };
+        let table_length = self.table_length;
+
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let result = submission
+                .map_or(Ok(()), |submission| Self::submit(&server, &submission))
+                .and_then(|()| Self::fetch(&server, table_length));
+            // Nobody waits for the answer anymore if a newer request was started meanwhile
+            let _ = sender.send(result);
+        });
+        // A running request is replaced, the new one fetches the boards as well
+        self.pending = Some(receiver);
+    }
+
+    /// Takes the answer of the server once it arrived, called every frame
+    pub fn poll(&mut self) {
+        let Some(receiver) = &self.pending else {
+            return;
+        };
+        match receiver.try_recv() {
+            Ok(result) => self.finish(result),
+            Err(TryRecvError::Empty) => {}
+            Err(TryRecvError::Disconnected) => self.pending = None,
+        }
+    }
+
+    /// Blocks until the running request to the server is answered
+    pub fn wait(&mut self) {
+        if let Some(result) = self.pending.as_ref().and_then(|receiver| receiver.recv().ok()) {
+            self.finish(result);
+        }
+        self.pending = None;
+    }
+
+    fn finish(&mut self, result: io::Result<Vec<Leaderboard>>) {
+        self.pending = None;
+        match result {
+            Ok(boards) => self.remote_boards = Some(boards),
+            Err(e) => {
+                let server = self.server.as_deref().unwrap_or_default();
+                eprintln!("Error synchronizing with the leaderboard server {}: {}, showing the local high scores", server, e);
+                self.remote_boards = None;
+            }
+        }
+    }
+
+    fn fetch(server: &str, table_length: usize) -> io::Result<Vec<Leaderboard>> {
+        let response = Self::agent()
+            .get(&format!("{server}/scores"))
+            .query("limit", &table_length.to_string())
+            .call()
+            .map_err(io::Error::other)?;
+        Ok(serde_json::from_reader(response.into_reader())?)
+    }
+
+    fn submit(server: &str, submission: &Submission) -> io::Result<()> {
+        Self::agent()
+            .post(&format!("{server}/scores"))
+            .set("Content-Type", "application/json")
+            .send_string(&serde_json::to_string(submission)?)
+            .map_err(io::Error::other)?;
+        Ok(())
+    }
+
+    fn agent() -> ureq::Agent {
+        ureq::AgentBuilder::new().timeout(SERVER_TIMEOUT).build()
+    }
+
+    /// `true` if the high scores can't be saved because a newer version of the game wrote them
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// The URL of the leaderboard server, if one is used
+    pub fn server(&self) -> Option<&str> {
+        self.server.as_deref()
+    }
+
+    pub fn server_status(&self) -> ServerStatus {
+        match (&self.server, &self.pending, &self.remote_boards) {
+            (None, _, _) => ServerStatus::Offline,
+            (Some(_), Some(_), _) => ServerStatus::Syncing,
+            (Some(_), None, Some(_)) => ServerStatus::Connected,
+            (Some(_), None, None) => ServerStatus::Unreachable,
+        }
+    }
+
+    pub fn board_count(&self) -> usize {
+        self.shown_boards().len()
+    }
+
+    pub fn board_title(&self, board: usize) -> String {
+        self.shown_boards().get(board).map(|board| board.key.title()).unwrap_or_default()
+    }
+
+    /// The entries of a board which fit into the table
+    fn entries(&self, board: usize) -> &[HighScoreEntry] {
+        self.shown_boards().get(board).map_or(&[], |board| &board.entries[..board.entries.len().min(self.table_length)])
+    }
+
+    pub fn display(&self, board: usize) -> Vec<String> {
         let mut strings: Vec<String> = Vec::new();
-        for (i, entry) in self.entries.iter().enumerate() {
-            strings.push(format!("{}: {} - {}", i + 1, entry.name, entry.score));
+        for (i, entry) in self.entries(board).iter().enumerate() {
+            let unverified = if entry.signature.is_empty() { " (UNVERIFIED)" } else { "" };
+            strings.push(format!("{}: {} - {}{}", i + 1, entry.name, entry.score, unverified));
         }
         strings
     }
-}
\ No newline at end of file
+
+    pub fn len(&self, board: usize) -> usize {
+        self.entries(board).len()
+    }
+
+    /// The statistics of the entry at `index` of `board` for the details view
+    pub fn details(&self, board: usize, index: usize) -> Vec<String> {
+        self.entries(board).get(index).map(|entry| entry.stats.details()).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+
+    /// An empty directory for the high scores of one test
+    fn directory(name: &str) -> PathBuf {
+        let directory = env::temp_dir().join(format!("codeinvaders-high-scores-{}-{name}", process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    fn add(high_scores: &mut HighScores, name: &str, score: i32) {
+        high_scores.add_score(&LeaderboardKey::legacy(), name.to_string(), score, GameStats::default(), name.to_string());
+    }
+
+    /// Names of the files in `directory` which start with `prefix`
+    fn files(directory: &Path, prefix: &str) -> Vec<String> {
+        fs::read_dir(directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|name| name.starts_with(prefix))
+            .collect()
+    }
+
+    #[test]
+    fn edited_statistics_break_the_signature() {
+        let key = LeaderboardKey::legacy();
+        let entry = HighScoreEntry::new("player".to_string(), 100, GameStats::new(3, 10, 7, 61.3), "hash".to_string(), &key);
+        let mut saved: HighScoreEntry = serde_json::from_str(&serde_json::to_string(&entry).unwrap()).unwrap();
+        assert_eq!(saved.check_signature(&key), Signature::Valid);
+
+        saved.stats.level = 20;
+        assert_eq!(saved.check_signature(&key), Signature::Invalid);
+    }
+
+    #[test]
+    fn corrupt_files_are_quarantined_and_the_backup_is_restored() {
+        let directory = directory("corrupt");
+        let mut high_scores = HighScores::new(&directory, 10);
+        add(&mut high_scores, "first", 100);
+        // The second save keeps the first one as the backup
+        add(&mut high_scores, "second", 200);
+        fs::write(directory.join(HighScores::FILE_NAME), "{ \"boards\": [").unwrap();
+
+        let mut loaded = HighScores::new(&directory, 10);
+        let error = loaded.load().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(loaded.display(0), vec!["1: first - 100"]);
+
+        let quarantined = files(&directory, "high_scores.json.corrupt-");
+        assert_eq!(quarantined.len(), 1);
+        assert_eq!(fs::read_to_string(directory.join(&quarantined[0])).unwrap(), "{ \"boards\": [");
+        assert!(!directory.join(HighScores::FILE_NAME).exists());
+    }
+
+    #[test]
+    fn files_of_newer_versions_are_kept_and_never_overwritten() {
+        let directory = directory("newer");
+        let path = directory.join(HighScores::FILE_NAME);
+        let newer = serde_json::json!({ "version": HighScores::SCHEMA_VERSION + 1, "boards": [] }).to_string();
+        fs::write(&path, &newer).unwrap();
+
+        let mut high_scores = HighScores::new(&directory, 10);
+        let error = high_scores.load().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::Unsupported);
+        assert!(error.to_string().contains("newer version"));
+        assert!(high_scores.is_read_only());
+        assert_eq!(high_scores.board_count(), 0);
+
+        // The score is shown for this session, but the file stays as it is
+        add(&mut high_scores, "player", 100);
+        assert_eq!(high_scores.display(0), vec!["1: player - 100"]);
+        assert_eq!(fs::read_to_string(&path).unwrap(), newer);
+        assert!(files(&directory, "high_scores.json.").is_empty());
+    }
+
+    #[test]
+    fn leftover_temporary_files_are_ignored_and_replaced() {
+        let directory = directory("temporary");
+        let mut high_scores = HighScores::new(&directory, 10);
+        add(&mut high_scores, "saved", 100);
+        // A save which crashed before the rename
+        let temporary_path = directory.join("high_scores.json.tmp");
+        fs::write(&temporary_path, "{ \"boa").unwrap();
+
+        let mut loaded = HighScores::new(&directory, 10);
+        loaded.load().unwrap();
+        assert_eq!(loaded.display(0), vec!["1: saved - 100"]);
+
+        add(&mut loaded, "next", 200);
+        assert!(!temporary_path.exists());
+        let mut reloaded = HighScores::new(&directory, 10);
+        reloaded.load().unwrap();
+        assert_eq!(reloaded.display(0), vec!["1: next - 200", "2: saved - 100"]);
+    }
+}
@@ -0,0 +1,105 @@
+use macroquad::prelude::*;
+use crate::replay::Replay;
+
+/// The player controls for a single simulation step.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Input {
+    pub left: bool,
+    pub right: bool,
+    pub fire: bool,
+    pub escape: bool,
+}
+
+impl Input {
+    const LEFT: u8 = 1;
+    const RIGHT: u8 = 1 << 1;
+    const FIRE: u8 = 1 << 2;
+    const ESCAPE: u8 = 1 << 3;
+
+    pub fn from_keyboard() -> Self {
+        Self {
+            left: is_key_down(KeyCode::Left),
+            right: is_key_down(KeyCode::Right),
+            fire: is_key_down(KeyCode::Space),
+            escape: is_key_pressed(KeyCode::Escape),
+        }
+    }
+
+    pub fn to_bits(self) -> u8 {
+        let mut bits = 0;
+        if self.left { bits |= Self::LEFT; }
+        if self.right { bits |= Self::RIGHT; }
+        if self.fire { bits |= Self::FIRE; }
+        if self.escape { bits |= Self::ESCAPE; }
+        bits
+    }
+
+    /// Returns `None` if unknown bits are set
+    pub fn from_bits(bits: u8) -> Option<Self> {
+        if bits & !(Self::LEFT | Self::RIGHT | Self::FIRE | Self::ESCAPE) != 0 {
+            return None;
+        }
+
+        Some(Self {
+            left: bits & Self::LEFT != 0,
+            right: bits & Self::RIGHT != 0,
+            fire: bits & Self::FIRE != 0,
+            escape: bits & Self::ESCAPE != 0,
+        })
+    }
+}
+
+/// Where the inputs for the simulation ticks come from.
+pub enum InputSource {
+    /// Reads the keyboard and records every tick
+    Keyboard { recording: Replay, escape_pressed: bool },
+    /// Plays back the ticks of a recorded game
+    Replay { replay: Replay, position: usize },
+}
+
+impl InputSource {
+    pub fn keyboard(recording: Replay) -> Self {
+        InputSource::Keyboard { recording, escape_pressed: false }
+    }
+
+    pub fn replay(replay: Replay) -> Self {
+        InputSource::Replay { replay, position: 0 }
+    }
+
+    pub fn is_replay(&self) -> bool {
+        matches!(self, InputSource::Replay { .. })
+    }
+
+    /// Has to be called once per frame. Key presses are only reported for the frame they
+    /// happened in, so they are kept until the next tick, even if this frame has none.
+    pub fn poll(&mut self) {
+        if let InputSource::Keyboard { escape_pressed, .. } = self {
+            *escape_pressed |= is_key_pressed(KeyCode::Escape);
+        }
+    }
+
+    /// The input for the next simulation tick, `None` once a replay is over.
+    pub fn next_tick(&mut self) -> Option<Input> {
+        match self {
+            InputSource::Keyboard { recording, escape_pressed } => {
+                let input = Input { escape: *escape_pressed, ..Input::from_keyboard() };
+                *escape_pressed = false;
+                recording.inputs.push(input);
+                Some(input)
+            }
+            InputSource::Replay { replay, position } => {
+                let input = replay.inputs.get(*position).copied();
+                *position += 1;
+                input
+            }
+        }
+    }
+
+    /// `true` if a replay has no inputs left
+    pub fn is_finished(&self) -> bool {
+        match self {
+            InputSource::Keyboard { .. } => false,
+            InputSource::Replay { replay, position } => *position >= replay.inputs.len(),
+        }
+    }
+}
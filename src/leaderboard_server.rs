@@ -0,0 +1,86 @@
+use std::fmt::Display;
+use std::io::{self, Read};
+use tiny_http::{Header, Method, Request, Response, Server};
+use crate::high_scores::{HighScores, Submission};
+
+/// Submissions are a single entry, anything bigger isn't one
+const MAX_BODY_LENGTH: u64 = 64 * 1024;
+
+/// Answers the requests of the leaderboard API until the server is closed:
+///
+/// * `GET /scores?limit=<n>` returns the best `n` entries of every board, all of them
+///   without a limit
+/// * `POST /scores` with a `Submission` adds a signed entry and returns its board, every
+///   game can only be submitted once
+pub fn run(server: &Server, high_scores: &mut HighScores) {
+    for mut request in server.incoming_requests() {
+        let (status, body) = handle(&mut request, high_scores);
+        // The header is valid ASCII, so it can't fail
+        let content_type = Header::from_bytes("Content-Type", "application/json").unwrap();
+        let response = Response::from_string(body).with_status_code(status).with_header(content_type);
+        if let Err(e) = request.respond(response) {
+            eprintln!("Error answering a request: {}", e);
+        }
+    }
+}
+
+fn handle(request: &mut Request, high_scores: &mut HighScores) -> (u16, String) {
+    let url = request.url().to_string();
+    let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+
+    match (request.method(), path) {
+        (Method::Get, "/scores") => {
+            let limit = query
+                .split('&')
+                .find_map(|parameter| parameter.strip_prefix("limit="))
+                .map_or(Ok(usize::MAX), |limit| limit.parse::<usize>());
+            match limit {
+                Ok(limit) => (200, to_json(&high_scores.top(limit))),
+                Err(e) => (400, error_body(format!("invalid limit: {e}"))),
+            }
+        }
+        (Method::Post, "/scores") => {
+            let mut body = String::new();
+            if let Err(e) = request.as_reader().take(MAX_BODY_LENGTH).read_to_string(&mut body) {
+                return (400, error_body(e));
+            }
+            let submission: Submission = match serde_json::from_str(&body) {
+                Ok(submission) => submission,
+                Err(e) => return (400, error_body(format!("invalid submission: {e}"))),
+            };
+
+            let key = submission.key;
+            let description = format!("{} - {}", submission.entry.name, submission.entry.score);
+            match high_scores.add_signed(&key, submission.entry) {
+                Ok(()) => {
+                    println!("Added {} to {}", description, key.title());
+                    let board = high_scores.top(usize::MAX).into_iter().find(|board| board.key == key);
+                    (200, to_json(&board))
+                }
+                Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                    eprintln!("Rejected {} for {}: {}", description, key.title(), e);
+                    (403, error_body(e))
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                    eprintln!("Rejected {} for {}: {}", description, key.title(), e);
+                    (409, error_body(e))
+                }
+                Err(e) => {
+                    eprintln!("Error saving high scores: {}", e);
+                    (500, error_body(e))
+                }
+            }
+        }
+        (_, "/scores") => (405, error_body("only GET and POST are allowed")),
+        _ => (404, error_body(format!("{path} not found"))),
+    }
+}
+
+fn to_json(value: &impl serde::Serialize) -> String {
+    // The high score types always serialize
+    serde_json::to_string(value).unwrap()
+}
+
+fn error_body(message: impl Display) -> String {
+    serde_json::json!({ "error": message.to_string() }).to_string()
+}
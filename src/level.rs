@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use macroquad::prelude::*;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use crate::dive::DivePattern;
+use crate::enemy::EnemyKind;
+
+/// A level as described by a JSON file in the `levels` directory.
+///
+/// Every string in `formation` is one row of enemies. A `.` or space is an empty cell,
+/// `#` is a plain enemy with the default `texture` of the level and every other character
+/// refers to an entry of `enemy_types`. A level with `boss` set has no formation, a single
+/// boss with the default `texture` appears instead.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Level {
+    pub name: String,
+    pub texture: String,
+    pub speed_multiplier: f32,
+    /// Average enemy shots per second
+    pub fire_rate: f32,
+    #[serde(default)]
+    pub enemy_types: HashMap<char, EnemyKind>,
+    #[serde(default)]
+    pub formation: Vec<String>,
+    /// Attack patterns the enemies pick from when they dive, no dives if empty
+    #[serde(default)]
+    pub dive_patterns: Vec<DivePattern>,
+    /// Average dives per second
+    #[serde(default)]
+    pub dive_rate: f32,
+    #[serde(default)]
+    pub boss: bool,
+    /// SHA-256 of the level file, see `World::levels_hash`
+    #[serde(skip)]
+    pub file_hash: [u8; 32],
+}
+
+#[derive(Debug)]
+pub struct LevelError {
+    pub path: PathBuf,
+    pub message: String,
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.message)
+    }
+}
+
+impl Level {
+    pub const DIRECTORY: &'static str = "levels";
+    /// Enemies are 1/20 of the playfield wide, 15 of them with their spacing already span
+    /// most of the width and leave the formation little room to march
+    const MAX_COLUMNS: usize = 15;
+    /// More rows would start the formation so low that it reaches the player within seconds.
+    /// The simulation isn't the limit, `cargo bench` steps 1000 smaller enemies well within a tick.
+    const MAX_ROWS: usize = 8;
+
+    /// Loads every `*.json` file of `directory` in alphabetical order. Files which can't be
+    /// loaded are skipped and reported in the returned errors.
+    pub fn load_all(directory: &Path, textures: &HashMap<String, Vec2>) -> (Vec<Level>, Vec<LevelError>) {
+        let mut levels = Vec::new();
+        let mut errors = Vec::new();
+
+        let mut paths = match fs::read_dir(directory) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().is_some_and(|extension| extension == "json"))
+                .collect::<Vec<PathBuf>>(),
+            Err(error) => {
+                errors.push(LevelError { path: directory.to_path_buf(), message: error.to_string() });
+                Vec::new()
+            }
+        };
+        paths.sort();
+
+        for path in paths {
+            match Self::load(&path, textures) {
+                Ok(level) => levels.push(level),
+                Err(error) => errors.push(error),
+            }
+        }
+
+        (levels, errors)
+    }
+
+    pub fn load(path: &Path, textures: &HashMap<String, Vec2>) -> Result<Level, LevelError> {
+        let error = |message: String| LevelError { path: path.to_path_buf(), message };
+
+        let contents = fs::read_to_string(path).map_err(|e| error(e.to_string()))?;
+        let mut level: Level = serde_json::from_str(&contents).map_err(|e| error(e.to_string()))?;
+        level.validate(textures).map_err(error)?;
+        level.file_hash = Sha256::digest(contents.as_bytes()).into();
+
+        Ok(level)
+    }
+
+    fn validate(&self, textures: &HashMap<String, Vec2>) -> Result<(), String> {
+        if self.speed_multiplier <= 0.0 {
+            return Err("speed_multiplier must be greater than 0".to_string());
+        }
+        if self.fire_rate < 0.0 {
+            return Err("fire_rate must not be negative".to_string());
+        }
+        if self.dive_rate < 0.0 {
+            return Err("dive_rate must not be negative".to_string());
+        }
+        if !textures.contains_key(&self.texture) {
+            return Err(format!("unknown texture '{}'", self.texture));
+        }
+        for (symbol, kind) in &self.enemy_types {
+            if !textures.contains_key(&kind.texture) {
+                return Err(format!("unknown texture '{}' for enemy type '{symbol}'", kind.texture));
+            }
+            if kind.hit_points == 0 {
+                return Err(format!("hit_points of enemy type '{symbol}' must be at least 1"));
+            }
+            if kind.speed_modifier <= 0.0 {
+                return Err(format!("speed_modifier of enemy type '{symbol}' must be greater than 0"));
+            }
+        }
+
+        if self.boss {
+            if !self.formation.is_empty() {
+                return Err("a boss level has no formation".to_string());
+            }
+            return Ok(());
+        }
+        if self.formation.is_empty() || self.formation.len() > Self::MAX_ROWS {
+            return Err(format!("formation must have between 1 and {} rows", Self::MAX_ROWS));
+        }
+        for (index, row) in self.formation.iter().enumerate() {
+            if row.chars().count() > Self::MAX_COLUMNS {
+                return Err(format!("row {} has more than {} columns", index + 1, Self::MAX_COLUMNS));
+            }
+            for symbol in row.chars() {
+                if !matches!(symbol, '.' | ' ' | '#') && !self.enemy_types.contains_key(&symbol) {
+                    return Err(format!("row {} uses undefined enemy type '{symbol}'", index + 1));
+                }
+            }
+        }
+        if self.cells().next().is_none() {
+            return Err("formation contains no enemies".to_string());
+        }
+
+        Ok(())
+    }
+
+    /// All occupied cells of the formation as `(column, row, symbol)`
+    pub fn cells(&self) -> impl Iterator<Item = (usize, usize, char)> + '_ {
+        self.formation.iter().enumerate().flat_map(|(row, cells)| {
+            cells.chars().enumerate()
+                .filter(|(_, symbol)| !matches!(symbol, '.' | ' '))
+                .map(move |(col, symbol)| (col, row, symbol))
+        })
+    }
+
+    /// The enemy kind a symbol of the formation stands for
+    pub fn kind(&self, symbol: char) -> Option<EnemyKind> {
+        match symbol {
+            '#' => Some(EnemyKind::with_texture(&self.texture)),
+            _ => self.enemy_types.get(&symbol).cloned(),
+        }
+    }
+}
@@ -0,0 +1,29 @@
+//! The game simulation, rendering and high scores of CodeInvaders, shared by the game, the
+//! leaderboard server, the tests and the benchmarks.
+pub mod player;
+pub mod dive;
+pub mod enemy;
+pub mod formation;
+pub mod bullet;
+pub mod boss;
+pub mod bunker;
+pub mod collision;
+pub mod collision_mask;
+pub mod floating_text;
+pub mod power_up;
+pub mod weapon;
+pub mod pool;
+pub mod ufo;
+pub mod spatial_grid;
+pub mod input;
+pub mod level;
+pub mod replay;
+pub mod score_keeper;
+pub mod world;
+pub mod timestep;
+pub mod renderer;
+pub mod data_dir;
+pub mod game_mode;
+pub mod high_scores;
+#[cfg(feature = "server")]
+pub mod leaderboard_server;
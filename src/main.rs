@@ -1,13 +1,17 @@
-use std::{collections::HashMap, rc::Rc};
+use std::path::{Path, PathBuf};
+use std::process;
 use macroquad::prelude::*;
-mod player;
-use player::Player;
-mod enemy;
-use enemy::Enemy;
-mod bullet;
-use bullet::Bullet;
-mod high_scores;
-use high_scores::HighScores;
+use codeinvaders::data_dir;
+use codeinvaders::game_mode::GameMode;
+use codeinvaders::high_scores::{GameStats, HighScores, LeaderboardKey, ServerStatus};
+use codeinvaders::input::InputSource;
+use codeinvaders::level::Level;
+use codeinvaders::renderer::{set_playfield_camera, Renderer};
+use codeinvaders::replay::Replay;
+use codeinvaders::timestep::FixedTimestep;
+use codeinvaders::world::{StepOutcome, World, PADDING, PLAYFIELD};
+mod options;
+use options::Options;
 mod name_input;
 use name_input::NameInput;
 
@@ -25,9 +29,9 @@ struct MenuText {
     font_size: u16,
 }
 
-const PADDING: f32 = 10.0;
-const FONT_SIZE_LARGE: u16 = 50;
-const FONT_SIZE_MEDIUM: u16 = 30;
+const FONT_SIZE_LARGE: u16 = 90;
+const FONT_SIZE_MEDIUM: u16 = 54;
+const FONT_SIZE_SMALL: u16 = 36;
 
 async fn draw_menu(texts: Vec<MenuText>) {
     let mut total_height = 0.0;
@@ -40,14 +44,14 @@ async fn draw_menu(texts: Vec<MenuText>) {
     total_height -= PADDING; // Remove the padding after the last item
 
     // Calculate starting y position to center all text items
-    let mut current_y = screen_height() / 2.0 - total_height / 2.0;
+    let mut current_y = PLAYFIELD.y / 2.0 - total_height / 2.0;
 
     // Draw each text item
     for menu_text in &texts {
         let text_size = measure_text(&menu_text.text, None, menu_text.font_size, 1.0);
         draw_text(
             &menu_text.text,
-            screen_width() / 2.0 - text_size.width / 2.0,
+            PLAYFIELD.x / 2.0 - text_size.width / 2.0,
             current_y,
             menu_text.font_size as f32,
             LIGHTGRAY,
@@ -56,218 +60,153 @@ async fn draw_menu(texts: Vec<MenuText>) {
     }
 }
 
-// Not used anymore, but I leave it here in case I want to use it in the future
-// when using this I need to add the assets first, by using something like this:
-// include_bytes!("..\\assets\\python.png")
-async fn _load_enemy_texture_from_binary(level: &i8, textures: &HashMap<&str, &[u8]>,) -> Texture2D {
-    Texture2D::from_file_with_format(
-        textures[
-            match *level {
-                1 => "python",
-                2 => "java",
-                3 => "dart",
-                4 => "cplusplus",
-                _ => "c",
-            }
-        ],
-        Some(ImageFormat::Png),
-    )
-}
-
-async fn load_texture_from_file(filename: &str) -> Texture2D {
-    let fileload_result = load_texture(&format!("assets/{}", filename)).await;
-
-    match fileload_result {
-        Ok(file) => file,
-        Err(error) => panic!("Problem opening the texture file: {error:?}"),
-    }
-}
-
-async fn load_enemy_texture(level: &i8) -> Texture2D {
-    let filename = match *level {
-        1 => "python.png",
-        2 => "java.png",
-        3 => "dart.png",
-        4 => "cplusplus.png",
-        _ => "c.png",
-    };
-
-    load_texture_from_file(&filename).await
-}
-
-async fn spawn_enemies(enemies: &mut Vec<Enemy>, level: &mut i8, texture: Texture2D, rows: i8) {
-    let scale = screen_width() / 20.0 / texture.width();
-    let enemy_width = texture.width() * scale;
-    let enemy_height = texture.height() * scale;
-    let spacing_x = enemy_width + PADDING;
-    let spacing_y = enemy_height + PADDING;
-
-    let texture_pointer = Rc::new(texture);
-
-    for row in 0..rows {
-        for col in 0..10 {
-            let x = col as f32 * spacing_x + PADDING + 1.0;
-            let y = row as f32 * spacing_y + PADDING;
-
-            enemies.push(Enemy::new(
-                Rc::clone(&texture_pointer),
-                scale,
-                *level,
-                vec2(x, y),
-            ));
-        }
-    }
-}
+async fn start_game(world: &mut World, input_source: &mut InputSource, timestep: &mut FixedTimestep, game_state: &mut GameState) {
+    let seed = miniquad::date::now() as u64;
 
-async fn init_game (
-    level: &mut i8,
-    player: &mut Player,
-    enemies: &mut Vec<Enemy>,
-    bullets: &mut Vec<Bullet>,
-    game_state: &mut GameState,
-) {
-    *level += 1;
-
-    player.reset();
-
-    bullets.clear();
-
-    enemies.clear();
-
-    let current_enemy_texture = load_enemy_texture(level).await;
-    spawn_enemies(enemies, level, current_enemy_texture, 5).await;
+    let difficulty = world.difficulty;
+    world.new_game(seed, 1, difficulty);
+    *input_source = InputSource::keyboard(Replay::new(seed, 1, difficulty, world.levels_hash()));
+    timestep.reset();
 
     *game_state = GameState::Playing;
 }
 
-async fn calculate_enemy_movement(enemies: &mut Vec<Enemy>, delta_time: &f32) {
-    let screen_width = screen_width();
-    let mut hit_wall = false;
-
-    let first_enemy = enemies.first().unwrap(); //We are sure enemies are always present → unwrap is safe
-    let enemy_width = first_enemy.texture.width() * first_enemy.scale;
-
-    // ToDo: Sometimes this is bugged and moves all enemies directly to the bottom
-    //       I assume the enemies get updated to often and glitch into the padding,
-    //       which triggers the direction change multiple times
-    for enemy in enemies.iter() {
-        if enemy.position.x + enemy_width >= screen_width - PADDING || enemy.position.x <= PADDING {
-            hit_wall = true;
-            break;
-        }
-    }
+async fn start_replay(world: &mut World, input_source: &mut InputSource, timestep: &mut FixedTimestep, game_state: &mut GameState, replay: Replay) {
+    world.new_game(replay.seed, replay.level, replay.difficulty);
+    *input_source = InputSource::replay(replay);
+    timestep.reset();
 
-    for enemy in enemies.iter_mut() {
-        enemy.update(&hit_wall, delta_time);
-        enemy.draw();
-    }
+    *game_state = GameState::Playing;
 }
 
-// ToDo: Possible optimization: use bullet pool instead of creating & deleting new bullets every time
-async fn shoot_bullet(bullets: &mut Vec<Bullet>, player: &mut Player, last_shot: &mut f64, delta_time: &f32, score: &mut i32) {
-    let bullet_shoot_speed = 50.0;
-    let current_time = get_time();
-    if is_key_down(KeyCode::Space) && current_time - *last_shot > (bullet_shoot_speed * *delta_time as f64) {
-        let bullet_position = vec2(
-            player.position.x + (player.texture.width() * player.scale) / 2.0,
-            player.position.y
-        );
-        bullets.push(Bullet::new(bullet_position));
-        *last_shot = current_time;
-        *score -= 1;
-    }
-}
+async fn init_game(world: &mut World, timestep: &mut FixedTimestep, game_state: &mut GameState) {
+    world.next_level();
+    timestep.reset();
 
-fn is_collision(bullet: &Bullet, enemy: &Enemy) -> bool {
-    bullet.position.x < enemy.position.x + enemy.texture.width() * enemy.scale &&   // Left
-    bullet.position.x > enemy.position.x &&                                         // Right
-    bullet.position.y < enemy.position.y + enemy.texture.height() * enemy.scale &&  // Top
-    bullet.position.y > enemy.position.y                                            // Bottom
+    *game_state = GameState::Playing;
 }
 
-async fn check_collision(bullets: &mut Vec<Bullet>, enemies: &mut Vec<Enemy>, score: &mut i32) {
-    for bullet in bullets.iter_mut() {
-        for enemy in enemies.iter_mut() {
-            if is_collision(bullet, enemy) {                                          // Bottom
-                bullet.collided = true;
-                enemy.collided = true;
-                *score += 10;
-            }
+/// Saves the recording of the finished game to `replay_directory`, so it can be attached to
+/// bug reports
+async fn end_game(game_state: &mut GameState, input_source: &InputSource, replay_directory: &Path) {
+    if let InputSource::Keyboard { recording, .. } = input_source {
+        let path = replay_directory
+            .join(format!("{}.{}", miniquad::date::now() as u64, Replay::EXTENSION));
+        match recording.save(&path) {
+            Ok(()) => println!("Replay saved to {}", path.display()),
+            Err(e) => eprintln!("Error saving replay: {}", e),
         }
     }
-    bullets.retain(|bullet| !bullet.collided);
-    enemies.retain(|enemy| !enemy.collided);
-}
 
-async fn check_round_finished(player: &Player, enemies: &Vec<Enemy>, game_state: &mut GameState) {
-    if enemies.is_empty() {
-        *game_state = GameState::LevelComplete;
-    }
-    
-    for enemy in enemies {
-        if enemy.position.y + enemy.texture.height() * enemy.scale >= player.position.y {
-            *game_state = GameState::EnterName;
-        }
-    }
+    *game_state = GameState::EnterName;
 }
 
-async fn handle_menu(game_state: &mut GameState, level: &mut i8, player: &mut Player, enemies: &mut Vec<Enemy>, bullets: &mut Vec<Bullet>) {
-    draw_menu(vec![
+async fn handle_menu(game_state: &mut GameState, world: &mut World, input_source: &mut InputSource, timestep: &mut FixedTimestep, warnings: &[String]) {
+    let mut menu_texts = vec![
         MenuText { text: "CodeInvaders".to_string(), font_size: FONT_SIZE_LARGE },
         MenuText { text: "Press ENTER to Start".to_string(), font_size: FONT_SIZE_MEDIUM },
         MenuText { text: "Press H to show highscores".to_string(), font_size: FONT_SIZE_MEDIUM },
-    ]).await;
+        MenuText { text: format!("DIFFICULTY: < {} >", world.difficulty.label()), font_size: FONT_SIZE_MEDIUM },
+        MenuText { text: "Press LEFT/RIGHT to change the difficulty".to_string(), font_size: FONT_SIZE_SMALL },
+    ];
+    for warning in warnings {
+        menu_texts.push(MenuText { text: warning.clone(), font_size: FONT_SIZE_SMALL });
+    }
+    draw_menu(menu_texts).await;
 
+    if is_key_pressed(KeyCode::Left) {
+        world.difficulty = world.difficulty.easier();
+    }
+    if is_key_pressed(KeyCode::Right) {
+        world.difficulty = world.difficulty.harder();
+    }
     if is_key_pressed(KeyCode::Enter) {
-        init_game(level, player, enemies, bullets, game_state).await;
+        start_game(world, input_source, timestep, game_state).await;
     }
     if is_key_pressed(KeyCode::H) {
         *game_state = GameState::HighScores;
     }
 }
 
-async fn handle_playing(game_state: &mut GameState, player: &mut Player, enemies: &mut Vec<Enemy>, bullets: &mut Vec<Bullet>, last_shot: &mut f64, delta_time: &f32, score: &mut i32) {
-    player.update(&delta_time);
-    player.draw();
+async fn handle_playing(game_state: &mut GameState, world: &mut World, renderer: &Renderer, input_source: &mut InputSource, timestep: &mut FixedTimestep, delta_time: f32, replay_directory: &Path) {
+    input_source.poll();
 
-    calculate_enemy_movement(enemies, &delta_time).await;
-
-    shoot_bullet(bullets, player, last_shot, &delta_time, score).await;
+    for _ in 0..timestep.advance(delta_time) {
+        let Some(input) = input_source.next_tick() else {
+            // The replay is over
+            end_game(game_state, input_source, replay_directory).await;
+            break;
+        };
+        if input.escape {
+            end_game(game_state, input_source, replay_directory).await;
+            break;
+        }
 
-    for bullet in bullets.iter_mut() {
-        bullet.update(&delta_time);
-        bullet.draw();
+        match world.step(&input, FixedTimestep::TICK) {
+            StepOutcome::Running => {}
+            StepOutcome::LevelComplete => {
+                *game_state = GameState::LevelComplete;
+                break;
+            }
+            StepOutcome::PlayerDefeated => {
+                end_game(game_state, input_source, replay_directory).await;
+                break;
+            }
+        }
     }
-    bullets.retain(|bullet| bullet.position.y < screen_height());
 
-    check_collision(bullets, enemies, score).await;
+    renderer.draw_world(world, timestep.alpha());
+}
 
-    check_round_finished(&player, &enemies, game_state).await;
+async fn handle_level_complete(game_state: &mut GameState, world: &mut World, input_source: &InputSource, timestep: &mut FixedTimestep, replay_directory: &Path) {
+    let score = &world.score;
+    let level = &score.level;
+    let title = if world.is_boss_level() { "BOSS DEFEATED!" } else { "LEVEL COMPLETE" };
+    let breakdown = [
+        ("ENEMIES".to_string(), level.enemies),
+        (format!("COMBO BONUS (BEST x{})", score.best_combo), level.combo),
+        ("BOSS".to_string(), level.boss),
+        ("UFO".to_string(), level.ufo),
+        (format!("ACCURACY {:.0}%", score.level_accuracy() * 100.0), level.accuracy),
+        (format!("TIME {:.0}s", score.level_time), level.time),
+        ("NO DAMAGE".to_string(), level.no_damage),
+    ];
 
-    if is_key_pressed(KeyCode::Escape) {
-        *game_state = GameState::EnterName;
+    let mut menu_texts = vec![
+        MenuText { text: title.to_string(), font_size: FONT_SIZE_LARGE },
+    ];
+    for (label, points) in breakdown {
+        // Only list what the player actually scored
+        if points > 0 {
+            menu_texts.push(MenuText { text: format!("{label}: +{points}"), font_size: FONT_SIZE_SMALL });
+        }
     }
-}
-
-async fn handle_level_complete(game_state: &mut GameState, level: &mut i8, player: &mut Player, enemies: &mut Vec<Enemy>, bullets: &mut Vec<Bullet>, score: &i32) {
-    draw_menu(vec![
-        MenuText { text: "LEVEL COMPLETE".to_string(), font_size: FONT_SIZE_LARGE },
-        MenuText { text: format!("SCORE: {score}"), font_size: FONT_SIZE_LARGE },
+    menu_texts.append(&mut vec![
+        MenuText { text: format!("LEVEL: +{}", level.total()), font_size: FONT_SIZE_MEDIUM },
+        MenuText { text: format!("SCORE: {}", score.total), font_size: FONT_SIZE_LARGE },
         MenuText { text: "Press ENTER to continue".to_string(), font_size: FONT_SIZE_MEDIUM },
         MenuText { text: "Press ESC to finish".to_string(), font_size: FONT_SIZE_MEDIUM },
-    ]).await;
+    ]);
+    draw_menu(menu_texts).await;
 
-    if is_key_pressed(KeyCode::Enter) {
-        init_game(level, player, enemies, bullets, game_state).await;
+    // A replay continues as long as there are recorded inputs left
+    let (continue_game, finish_game) = if input_source.is_replay() {
+        (!input_source.is_finished(), input_source.is_finished())
+    } else {
+        (is_key_pressed(KeyCode::Enter), is_key_pressed(KeyCode::Escape))
+    };
+
+    if continue_game {
+        init_game(world, timestep, game_state).await;
         return;
     }
-    if is_key_pressed(KeyCode::Escape) {
-        *level = 0;
-        *game_state = GameState::EnterName;
+    if finish_game {
+        end_game(game_state, input_source, replay_directory).await;
     }
 }
 
-async fn handle_game_over(game_state: &mut GameState, score: &mut i32, level: &mut i8) {
+async fn handle_game_over(game_state: &mut GameState, world: &mut World) {
+    let score = world.score.total;
     draw_menu(vec![
         MenuText { text: "GAME OVER".to_string(), font_size: FONT_SIZE_LARGE },
         MenuText { text: format!("SCORE: {score}"), font_size: FONT_SIZE_LARGE },
@@ -277,20 +216,25 @@ async fn handle_game_over(game_state: &mut GameState, score: &mut i32, level: &m
 
     if is_key_pressed(KeyCode::Escape) ||
        is_key_pressed(KeyCode::Enter) {
-        *level = 0;
-        *score = 0;
+        world.reset();
         *game_state = GameState::Menu;
         return;
     }
     if is_key_pressed(KeyCode::H) {
-        *level = 0;
-        *score = 0;
+        world.reset();
         *game_state = GameState::HighScores;
     }
 }
 
-async fn handle_enter_name(game_state: &mut GameState, high_scores: &mut HighScores, name_input: &mut NameInput, score: &i32) {
-    if !high_scores.qualifies(*score) {
+async fn handle_enter_name(game_state: &mut GameState, high_scores: &mut HighScores, name_input: &mut NameInput, input_source: &InputSource, world: &World, level_set: &str) {
+    let score = &world.score;
+    // Replayed games don't count for the high scores
+    let InputSource::Keyboard { recording, .. } = input_source else {
+        *game_state = GameState::GameOver;
+        return;
+    };
+    let key = LeaderboardKey { mode: GameMode::Classic, difficulty: world.difficulty, level_set: level_set.to_string() };
+    if !high_scores.qualifies(&key, score.total) {
         *game_state = GameState::GameOver;
         return;
     }
@@ -299,24 +243,69 @@ async fn handle_enter_name(game_state: &mut GameState, high_scores: &mut HighSco
     name_input.draw();
 
     if is_key_pressed(KeyCode::Enter) {
-        high_scores.add_score(name_input.name.clone(), *score);
+        let stats = GameStats::new(world.level, score.shots, score.hits, score.play_time);
+        high_scores.add_score(&key, name_input.name.clone(), score.total, stats, recording.hash());
         *game_state = GameState::GameOver;
     }
 }
 
-async fn handle_high_scores(game_state: &mut GameState, high_scores: &HighScores) {
-    let mut scores = high_scores.display().iter().map(
-        |entry| MenuText { text: entry.clone(), font_size: FONT_SIZE_MEDIUM }
-    ).collect::<Vec<MenuText>>();
+/// Scores shown at once, longer tables scroll with the selection
+const VISIBLE_SCORES: usize = 10;
 
+async fn handle_high_scores(game_state: &mut GameState, high_scores: &HighScores, board: &mut usize, selected: &mut usize) {
+    // Every leaderboard is a tab
+    let board_count = high_scores.board_count();
+    if is_key_pressed(KeyCode::Right) && board_count > 0 {
+        *board = (*board + 1) % board_count;
+        *selected = 0;
+    }
+    if is_key_pressed(KeyCode::Left) && board_count > 0 {
+        *board = (*board + board_count - 1) % board_count;
+        *selected = 0;
+    }
+    *board = (*board).min(board_count.saturating_sub(1));
+
+    if is_key_pressed(KeyCode::Down) && *selected + 1 < high_scores.len(*board) {
+        *selected += 1;
+    }
+    if is_key_pressed(KeyCode::Up) {
+        *selected = selected.saturating_sub(1);
+    }
+
+    let first_visible = (*selected + 1).saturating_sub(VISIBLE_SCORES);
+    let mut scores = high_scores.display(*board).iter().enumerate().skip(first_visible).take(VISIBLE_SCORES).map(|(index, entry)| {
+        let text = if index == *selected { format!("> {entry} <") } else { entry.clone() };
+        MenuText { text, font_size: FONT_SIZE_MEDIUM }
+    }).collect::<Vec<MenuText>>();
+
+    let title = if board_count == 0 {
+        "No high scores yet".to_string()
+    } else {
+        format!("< {} >  ({}/{})", high_scores.board_title(*board), *board + 1, board_count)
+    };
     let mut menu_texts = vec![
         MenuText { text: "HIGH SCORES".to_string(), font_size: FONT_SIZE_LARGE },
-        MenuText { text: "Press ESC to exit".to_string(), font_size: FONT_SIZE_MEDIUM },
-        MenuText { text: " ".to_string(), font_size: FONT_SIZE_MEDIUM },
+        MenuText { text: "Press ESC to exit, LEFT/RIGHT to change the board, UP/DOWN to select".to_string(), font_size: FONT_SIZE_SMALL },
+        MenuText { text: title, font_size: FONT_SIZE_MEDIUM },
     ];
+    if let Some(server) = high_scores.server() {
+        let text = match high_scores.server_status() {
+            ServerStatus::Syncing => format!("Synchronizing with {server}..."),
+            ServerStatus::Connected => format!("Leaderboard of {server}"),
+            _ => format!("{server} can't be reached, showing the local high scores"),
+        };
+        menu_texts.push(MenuText { text, font_size: FONT_SIZE_SMALL });
+    }
+    menu_texts.push(MenuText { text: " ".to_string(), font_size: FONT_SIZE_MEDIUM });
 
     menu_texts.append(&mut scores);
 
+    // Details of the selected entry
+    menu_texts.push(MenuText { text: " ".to_string(), font_size: FONT_SIZE_SMALL });
+    for line in high_scores.details(*board, *selected) {
+        menu_texts.push(MenuText { text: line, font_size: FONT_SIZE_SMALL });
+    }
+
     draw_menu(menu_texts).await;
 
     if is_key_pressed(KeyCode::Escape) {
@@ -324,36 +313,137 @@ async fn handle_high_scores(game_state: &mut GameState, high_scores: &HighScores
     }
 }
 
-#[macroquad::main("CodeInvaders")]
-async fn main() {
+/// Shows a problem which keeps the game from starting until ESC is pressed
+async fn show_startup_error(lines: Vec<String>) {
+    loop {
+        set_playfield_camera();
+        clear_background(BLACK);
+
+        let mut menu_texts = vec![MenuText { text: "CodeInvaders can't start".to_string(), font_size: FONT_SIZE_LARGE }];
+        for line in &lines {
+            menu_texts.push(MenuText { text: line.clone(), font_size: FONT_SIZE_SMALL });
+        }
+        menu_texts.push(MenuText { text: "Press ESC to exit".to_string(), font_size: FONT_SIZE_MEDIUM });
+        draw_menu(menu_texts).await;
+
+        if is_key_pressed(KeyCode::Escape) {
+            return;
+        }
+        next_frame().await
+    }
+}
+
+/// Checks the signatures of the high scores for the `verify-scores` subcommand and exits
+fn verify_scores(data_dir: &Path) -> ! {
+    let high_scores = HighScores::new(data_dir, HighScores::DEFAULT_TABLE_LENGTH);
+    match high_scores.verify() {
+        Ok(true) => {
+            println!("All high scores are signed correctly");
+            process::exit(0);
+        }
+        Ok(false) => {
+            println!("Some high scores are unsigned or were edited");
+            process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("Error reading high scores: {}", e);
+            process::exit(2);
+        }
+    }
+}
+
+fn main() {
+    let options = Options::parse(std::env::args().skip(1)).unwrap_or_else(|error| {
+        eprintln!("{error}\n{}", Options::USAGE);
+        process::exit(2);
+    });
+    let data_dir = data_dir::resolve(options.data_dir.as_deref());
+    if options.verify_scores {
+        verify_scores(&data_dir);
+    }
+
+    // The window is only opened once it is clear that the game is started
+    macroquad::Window::new("CodeInvaders", run(options, data_dir));
+}
+
+async fn run(options: Options, data_dir: PathBuf) {
     // Initiate globaly needed game assets
     let mut delta_time;
     let mut game_state = GameState::Menu;
-    let mut level: i8 = 0;
-    let mut score: i32 = 0;
-    let mut high_scores = HighScores::new();
-    high_scores.load().unwrap_or_default(); // right now i implemented this to always return true, so no use of error handling
+    let table_length = options.table_length.unwrap_or(HighScores::DEFAULT_TABLE_LENGTH);
+    // Problems which don't stop the game, shown in the main menu
+    let mut warnings = Vec::new();
+    let mut high_scores = HighScores::new(&data_dir, table_length);
+    let replay_directory = data_dir.join(Replay::DIRECTORY);
+    if let Err(e) = high_scores.import_legacy() {
+        eprintln!("Error importing the high scores of an older version: {}", e);
+    }
+    if let Err(e) = high_scores.load() {
+        eprintln!("Error loading high scores: {}", e);
+        if high_scores.is_read_only() {
+            warnings.push("The high scores belong to a newer version of the game, new scores won't be saved".to_string());
+        } else {
+            warnings.push("The high scores could not be loaded correctly, see console".to_string());
+        }
+    }
+    if let Some(url) = &options.server {
+        high_scores.connect(url);
+    }
     let mut name_input = NameInput::new();
-    let mut player = Player::new(load_texture_from_file("rust.png").await);
-    let mut enemies: Vec<Enemy> = Vec::new();
-    let mut bullets: Vec<Bullet> = Vec::new();
-    let mut last_shot = get_time();
+    let mut selected_board = 0;
+    let mut selected_score = 0;
+    let renderer = Renderer::load().await;
+    let texture_sizes = renderer.texture_sizes();
+    let levels_directory = options.levels.clone().unwrap_or_else(|| PathBuf::from(Level::DIRECTORY));
+    // Every level directory has its own leaderboards
+    let level_set = levels_directory.file_name().map_or(Level::DIRECTORY.into(), |name| name.to_string_lossy());
+    let (levels, level_errors) = Level::load_all(&levels_directory, &texture_sizes);
+    for error in &level_errors {
+        eprintln!("Error loading level: {}", error);
+    }
+    if !level_errors.is_empty() {
+        warnings.push(format!("{} level file(s) could not be loaded, see console", level_errors.len()));
+    }
+    if levels.is_empty() {
+        eprintln!("No valid level found in the '{}' directory", levels_directory.display());
+        show_startup_error(vec![
+            format!("No valid level found in the '{}' directory", levels_directory.display()),
+            "Make sure the levels directory is next to the game, see console for details".to_string(),
+        ]).await;
+        return;
+    }
+    let mut world = World::new(PLAYFIELD, texture_sizes, renderer.collision_masks(), levels);
+    let mut timestep = FixedTimestep::new();
+    let mut input_source = InputSource::keyboard(Replay::new(0, 1, world.difficulty, world.levels_hash()));
+
+    if let Some(path) = &options.replay {
+        let replay = Replay::load(path).unwrap_or_else(|error| {
+            eprintln!("Problem loading the replay {}: {error}", path.display());
+            process::exit(1);
+        });
+        if let Err(error) = replay.check_compatible(&world.levels_hash()) {
+            eprintln!("Can't play the replay {}: {error}", path.display());
+            process::exit(1);
+        }
+        start_replay(&mut world, &mut input_source, &mut timestep, &mut game_state, replay).await;
+    }
 
     // Run game
     clear_background(BLACK);
     loop {
         delta_time = get_frame_time();
+        set_playfield_camera();
+        high_scores.poll();
 
         match game_state {
-            // ToDo: This looks pretty bad. Find a better way to handle globaly needed stuff like the enemy list
-            GameState::Menu => handle_menu(&mut game_state, &mut level, &mut player, &mut enemies, &mut bullets).await,
-            GameState::Playing => handle_playing(&mut game_state, &mut player, &mut enemies, &mut bullets, &mut last_shot, &delta_time, &mut score).await,
-            GameState::LevelComplete => handle_level_complete(&mut game_state, &mut level, &mut player, &mut enemies, &mut bullets, &score).await,
-            GameState::GameOver => handle_game_over(&mut game_state, &mut score, &mut level).await,
-            GameState::EnterName => handle_enter_name(&mut game_state, &mut high_scores, &mut name_input, &score).await,
-            GameState::HighScores => handle_high_scores(&mut game_state, &high_scores).await,
+            GameState::Menu => handle_menu(&mut game_state, &mut world, &mut input_source, &mut timestep, &warnings).await,
+            GameState::Playing => handle_playing(&mut game_state, &mut world, &renderer, &mut input_source, &mut timestep, delta_time, &replay_directory).await,
+            GameState::LevelComplete => handle_level_complete(&mut game_state, &mut world, &input_source, &mut timestep, &replay_directory).await,
+            GameState::GameOver => handle_game_over(&mut game_state, &mut world).await,
+            GameState::EnterName => handle_enter_name(&mut game_state, &mut high_scores, &mut name_input, &input_source, &world, &level_set).await,
+            GameState::HighScores => handle_high_scores(&mut game_state, &high_scores, &mut selected_board, &mut selected_score).await,
             }
 
         next_frame().await
     }
-}
\ No newline at end of file
+}
@@ -1,4 +1,5 @@
 use macroquad::prelude::*;
+use codeinvaders::world::PLAYFIELD;
 
 pub struct NameInput {
     pub name: String,
@@ -12,8 +13,7 @@ impl NameInput {
     pub fn update(&mut self) {
         if let Some(c) = get_char_pressed() {
             if c == '\n' {
-                // Enter key pressed
-                return;
+                // Enter key pressed, handled by the caller
             } else if c == '\u{8}' {
                 // Backspace key pressed
                 self.name.pop();
@@ -30,17 +30,17 @@ impl NameInput {
     
         draw_text(
             prompt,
-            screen_width() / 2.0 - measure_text(prompt, None, 30, 1.0).width / 2.0,
-            screen_height() / 2.0 - 20.0,
-            30.0,
+            PLAYFIELD.x / 2.0 - measure_text(prompt, None, 54, 1.0).width / 2.0,
+            PLAYFIELD.y / 2.0 - 36.0,
+            54.0,
             LIGHTGRAY,
         );
     
         draw_text(
             name,
-            screen_width() / 2.0 - measure_text(name, None, 30, 1.0).width / 2.0,
-            screen_height() / 2.0 + 20.0,
-            30.0,
+            PLAYFIELD.x / 2.0 - measure_text(name, None, 54, 1.0).width / 2.0,
+            PLAYFIELD.y / 2.0 + 36.0,
+            54.0,
             LIGHTGRAY,
         );
     }
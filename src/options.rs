@@ -0,0 +1,60 @@
+use std::path::PathBuf;
+
+/// Command line options of the game
+#[derive(Debug, Default)]
+pub struct Options {
+    /// Replay file to play back instead of reading the keyboard
+    pub replay: Option<PathBuf>,
+    /// Directory for the high scores instead of the default data directory
+    pub data_dir: Option<PathBuf>,
+    /// Directory to load the levels from instead of `levels`, every directory is a level
+    /// set with its own leaderboards
+    pub levels: Option<PathBuf>,
+    /// Entries of every leaderboard instead of the default of 10
+    pub table_length: Option<usize>,
+    /// URL of a leaderboard server to submit the high scores to
+    pub server: Option<String>,
+    /// Check the signatures of the high scores instead of starting the game
+    pub verify_scores: bool,
+}
+
+impl Options {
+    pub const USAGE: &'static str = "Usage: CodeInvaders [verify-scores] [--replay <file>] [--data-dir <directory>] [--levels <directory>] [--table-length <entries>] [--server <url>]";
+
+    pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut options = Options::default();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "verify-scores" => options.verify_scores = true,
+                "--replay" => {
+                    let path = args.next().ok_or("--replay needs a file")?;
+                    options.replay = Some(PathBuf::from(path));
+                }
+                "--data-dir" => {
+                    let path = args.next().ok_or("--data-dir needs a directory")?;
+                    options.data_dir = Some(PathBuf::from(path));
+                }
+                "--levels" => {
+                    let path = args.next().ok_or("--levels needs a directory")?;
+                    options.levels = Some(PathBuf::from(path));
+                }
+                "--server" => {
+                    let url = args.next().ok_or("--server needs a URL")?;
+                    options.server = Some(url);
+                }
+                "--table-length" => {
+                    let length = args
+                        .next()
+                        .and_then(|length| length.parse::<usize>().ok())
+                        .filter(|&length| length > 0)
+                        .ok_or("--table-length needs a number of entries greater than 0")?;
+                    options.table_length = Some(length);
+                }
+                _ => return Err(format!("Unknown argument '{arg}'")),
+            }
+        }
+
+        Ok(options)
+    }
+}
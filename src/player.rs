@@ -1,65 +1,88 @@
 use macroquad::prelude::*;
+use crate::input::Input;
+use crate::power_up::ActivePowerUps;
+use crate::weapon::Weapon;
 
 pub struct Player {
-    pub texture: Texture2D,
+    pub texture: String,
+    pub texture_size: Vec2,
     pub scale: f32,
     pub position: Vec2,
+    pub previous_position: Vec2,
+    pub lives: u8,
+    /// Seconds left until the player can be hit again after losing a life
+    pub invulnerable: f32,
+    pub power_ups: ActivePowerUps,
+    pub weapon: Weapon,
 }
 
 impl Player {
-    pub fn new(texture: Texture2D) -> Self {
-        let scale = screen_width() / 15.0 / texture.width();
+    pub const START_LIVES: u8 = 3;
+    const RESPAWN_INVULNERABILITY: f32 = 2.0;
 
-        let position = vec2(
-            screen_width() / 2.0 - (texture.width() * scale) / 2.0,
-            screen_height() - (texture.height() * scale) - 10.0,
-        );
-
-        Self {
-            texture,
-            scale,
-            position,
-        }
+    pub fn new(texture: &str, texture_size: Vec2, playfield: Vec2) -> Self {
+        let mut player = Self {
+            texture: texture.to_string(),
+            texture_size,
+            scale: 1.0,
+            position: Vec2::ZERO,
+            previous_position: Vec2::ZERO,
+            lives: Self::START_LIVES,
+            invulnerable: 0.0,
+            power_ups: ActivePowerUps::default(),
+            weapon: Weapon::new(),
+        };
+        player.reset(playfield);
+        player
     }
 
-    pub fn draw(&self) {
-        draw_texture_ex(
-            &self.texture,
-            self.position.x,
-            self.position.y,
-            WHITE,
-            DrawTextureParams {
-                dest_size: Some(Vec2::new(self.texture.width() * self.scale, self.texture.height() * self.scale)),
-                ..Default::default()
-            },
-        );
+    pub fn size(&self) -> Vec2 {
+        self.texture_size * self.scale
     }
 
-    pub fn reset(&mut self) {
-        self.scale = screen_width() / 15.0 / self.texture.width();
+    pub fn reset(&mut self, playfield: Vec2) {
+        self.scale = playfield.x / 15.0 / self.texture_size.x;
 
         self.position = vec2(
-            screen_width() / 2.0 - (self.texture.width() * self.scale) / 2.0,
-            screen_height() - (self.texture.height() * self.scale) - 10.0,
+            playfield.x / 2.0 - self.size().x / 2.0,
+            playfield.y - self.size().y - 10.0,
         );
+        self.previous_position = self.position;
+        self.weapon.reset();
+    }
+
+    pub fn is_invulnerable(&self) -> bool {
+        self.invulnerable > 0.0
+    }
+
+    /// Takes away one life and all power-ups and respawns the player in the middle of the playfield.
+    pub fn hit(&mut self, playfield: Vec2) {
+        self.lives = self.lives.saturating_sub(1);
+        self.power_ups.clear();
+        self.reset(playfield);
+        self.invulnerable = Self::RESPAWN_INVULNERABILITY;
     }
 
-    pub fn update(&mut self, delta_time: &f32) {
-        let move_speed = screen_width() / 3.0;
+    pub fn update(&mut self, input: &Input, delta_time: f32, playfield: Vec2) {
+        self.invulnerable = (self.invulnerable - delta_time).max(0.0);
+        self.power_ups.update(delta_time);
+        self.previous_position = self.position;
+
+        let move_speed = playfield.x / 3.0;
         let left_bound = 10.0;
-        let right_bound = screen_width() - (self.texture.width() * self.scale) - 10.0;
+        let right_bound = playfield.x - self.size().x - 10.0;
 
-        if is_key_down(KeyCode::Left) {
-            self.position.x -= move_speed * *delta_time;
+        if input.left {
+            self.position.x -= move_speed * delta_time;
             if self.position.x < left_bound {
                 self.position.x = left_bound;
             }
         }
-        if is_key_down(KeyCode::Right) {
-            self.position.x += move_speed * *delta_time;
+        if input.right {
+            self.position.x += move_speed * delta_time;
             if self.position.x > right_bound {
                 self.position.x = right_bound;
             }
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,150 @@
+/// A stable reference to a value in a `Pool`. The generation makes sure that a handle of a
+/// removed value never refers to a newer value which reuses the same slot.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Handle {
+    index: u32,
+    generation: u32,
+}
+
+struct Slot<T> {
+    generation: u32,
+    value: Option<T>,
+}
+
+/// A fixed-capacity pool of values with generational handles.
+///
+/// All memory is allocated up front, inserting and removing values only moves them in and
+/// out of their slots. Besides the capacity the number of active values can be limited
+/// further with `set_max_active`, inserting into a full pool fails.
+pub struct Pool<T> {
+    slots: Vec<Slot<T>>,
+    /// Indices of the unused slots, the next one to use is at the end
+    free: Vec<u32>,
+    max_active: usize,
+}
+
+impl<T> Pool<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            slots: (0..capacity).map(|_| Slot { generation: 0, value: None }).collect(),
+            free: (0..capacity as u32).rev().collect(),
+            max_active: capacity,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.slots.len() - self.free.len()
+    }
+
+    /// Limits the number of active values, at most to the capacity
+    pub fn set_max_active(&mut self, max_active: usize) {
+        self.max_active = max_active.min(self.slots.len());
+    }
+
+    /// How many more values can be inserted before the pool is full
+    pub fn available(&self) -> usize {
+        self.max_active.saturating_sub(self.len())
+    }
+
+    /// Moves the value into a free slot, returns `None` if the pool is full
+    pub fn insert(&mut self, value: T) -> Option<Handle> {
+        if self.available() == 0 {
+            return None;
+        }
+
+        let index = self.free.pop()?;
+        let slot = &mut self.slots[index as usize];
+        slot.value = Some(value);
+        Some(Handle { index, generation: slot.generation })
+    }
+
+    fn release(&mut self, index: usize) {
+        let slot = &mut self.slots[index];
+        slot.value = None;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(index as u32);
+    }
+
+    /// Whether the value `handle` refers to is still in the pool
+    pub fn contains(&self, handle: Handle) -> bool {
+        self.slots
+            .get(handle.index as usize)
+            .is_some_and(|slot| slot.value.is_some() && slot.generation == handle.generation)
+    }
+
+    /// All active values in slot order, which doesn't change while they are active
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.slots.iter().filter_map(|slot| slot.value.as_ref())
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Handle, &mut T)> {
+        self.slots.iter_mut().enumerate().filter_map(|(index, slot)| {
+            let generation = slot.generation;
+            slot.value.as_mut().map(|value| (Handle { index: index as u32, generation }, value))
+        })
+    }
+
+    /// Removes all values for which `keep` returns `false`
+    pub fn retain(&mut self, mut keep: impl FnMut(&T) -> bool) {
+        for index in 0..self.slots.len() {
+            if self.slots[index].value.as_ref().is_some_and(|value| !keep(value)) {
+                self.release(index);
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.retain(|_| false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn handles_of_removed_values_dont_refer_to_reused_slots() {
+        let mut pool = Pool::new(1);
+        let old = pool.insert(1).unwrap();
+        pool.retain(|_| false);
+        assert!(!pool.contains(old));
+
+        let new = pool.insert(2).unwrap();
+        assert_eq!(new.index, old.index);
+        assert!(pool.contains(new));
+        assert!(!pool.contains(old));
+    }
+
+    #[test]
+    fn full_pools_reject_values() {
+        let mut pool = Pool::new(3);
+        pool.set_max_active(2);
+        assert!(pool.insert(1).is_some());
+        assert!(pool.insert(2).is_some());
+        assert_eq!(pool.insert(3), None);
+        assert_eq!(pool.available(), 0);
+
+        // The limit can't exceed the capacity
+        pool.set_max_active(10);
+        assert!(pool.insert(3).is_some());
+        assert_eq!(pool.insert(4), None);
+        assert_eq!(pool.iter().copied().collect::<Vec<_>>(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn removed_values_free_their_slots() {
+        let mut pool = Pool::new(3);
+        let handles: Vec<Handle> = (1..=3).map(|value| pool.insert(value).unwrap()).collect();
+
+        pool.retain(|&value| value != 2);
+        assert_eq!(pool.available(), 1);
+        assert!(pool.contains(handles[0]) && !pool.contains(handles[1]) && pool.contains(handles[2]));
+        assert!(pool.insert(4).is_some());
+        assert_eq!(pool.iter().copied().collect::<Vec<_>>(), [1, 4, 3]);
+
+        pool.clear();
+        assert_eq!(pool.available(), 3);
+        assert_eq!(pool.iter().count(), 0);
+        assert!((5..=7).all(|value| pool.insert(value).is_some()));
+    }
+}
@@ -0,0 +1,112 @@
+use macroquad::prelude::*;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PowerUpKind {
+    /// Fires three bullets in a fan
+    SpreadShot,
+    /// Shortens the time between two shots
+    RapidFire,
+    /// Every shot becomes a burst of three
+    BurstFire,
+    /// Bullets fly through enemies instead of stopping at the first one
+    Piercing,
+    /// Enemy bullets can't hurt the player
+    Shield,
+    /// One more life, no duration
+    ExtraLife,
+}
+
+impl PowerUpKind {
+    pub const ALL: [PowerUpKind; 6] = [
+        PowerUpKind::SpreadShot,
+        PowerUpKind::RapidFire,
+        PowerUpKind::BurstFire,
+        PowerUpKind::Piercing,
+        PowerUpKind::Shield,
+        PowerUpKind::ExtraLife,
+    ];
+
+    /// How long the power-up lasts in seconds
+    pub fn duration(&self) -> f32 {
+        match self {
+            PowerUpKind::SpreadShot => 10.0,
+            PowerUpKind::RapidFire => 10.0,
+            PowerUpKind::BurstFire => 10.0,
+            PowerUpKind::Piercing => 8.0,
+            PowerUpKind::Shield => 8.0,
+            PowerUpKind::ExtraLife => 0.0,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PowerUpKind::SpreadShot => "SPREAD",
+            PowerUpKind::RapidFire => "RAPID",
+            PowerUpKind::BurstFire => "BURST",
+            PowerUpKind::Piercing => "PIERCE",
+            PowerUpKind::Shield => "SHIELD",
+            PowerUpKind::ExtraLife => "LIFE",
+        }
+    }
+}
+
+/// A power-up dropped by a destroyed enemy, falling down until the player collects it.
+pub struct PowerUp {
+    pub kind: PowerUpKind,
+    pub position: Vec2,
+    pub previous_position: Vec2,
+    pub collected: bool,
+}
+
+impl PowerUp {
+    pub const SIZE: Vec2 = Vec2::new(40.0, 40.0);
+
+    pub fn new(kind: PowerUpKind, position: Vec2) -> Self {
+        Self {
+            kind,
+            position,
+            previous_position: position,
+            collected: false,
+        }
+    }
+
+    pub fn update(&mut self, delta_time: f32, playfield: Vec2) {
+        self.previous_position = self.position;
+        self.position.y += playfield.y / 5.0 * delta_time;
+    }
+}
+
+/// The power-ups the player has collected and the seconds they are still active.
+#[derive(Default)]
+pub struct ActivePowerUps {
+    remaining: Vec<(PowerUpKind, f32)>,
+}
+
+impl ActivePowerUps {
+    /// Starts the power-up or restarts its duration if it is already active
+    pub fn activate(&mut self, kind: PowerUpKind) {
+        match self.remaining.iter_mut().find(|(active, _)| *active == kind) {
+            Some((_, remaining)) => *remaining = kind.duration(),
+            None => self.remaining.push((kind, kind.duration())),
+        }
+    }
+
+    pub fn is_active(&self, kind: PowerUpKind) -> bool {
+        self.remaining.iter().any(|(active, _)| *active == kind)
+    }
+
+    pub fn update(&mut self, delta_time: f32) {
+        for (_, remaining) in self.remaining.iter_mut() {
+            *remaining -= delta_time;
+        }
+        self.remaining.retain(|(_, remaining)| *remaining > 0.0);
+    }
+
+    pub fn clear(&mut self) {
+        self.remaining.clear();
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(PowerUpKind, f32)> {
+        self.remaining.iter()
+    }
+}
@@ -0,0 +1,268 @@
+use std::collections::HashMap;
+use std::fs;
+use macroquad::prelude::*;
+use crate::bullet::{Bullet, Owner};
+use crate::collision_mask::CollisionMask;
+use crate::floating_text::FloatingText;
+use crate::power_up::{PowerUp, PowerUpKind};
+use crate::ufo::Ufo;
+use crate::world::{World, PADDING, PLAYFIELD};
+
+const ASSETS_DIRECTORY: &str = "assets";
+const HUD_FONT_SIZE: u16 = 36;
+
+// Not used anymore, but I leave it here in case I want to use it in the future
+// when using this I need to add the assets first, by using something like this:
+// include_bytes!("..\\assets\\python.png")
+async fn _load_enemy_texture_from_binary(level: &i8, textures: &HashMap<&str, &[u8]>,) -> Texture2D {
+    Texture2D::from_file_with_format(
+        textures[
+            match *level {
+                1 => "python",
+                2 => "java",
+                3 => "dart",
+                4 => "cplusplus",
+                _ => "c",
+            }
+        ],
+        Some(ImageFormat::Png),
+    )
+}
+
+async fn load_texture_from_file(filename: &str) -> Texture2D {
+    let fileload_result = load_texture(&format!("{ASSETS_DIRECTORY}/{filename}")).await;
+
+    match fileload_result {
+        Ok(file) => file,
+        Err(error) => panic!("Problem opening the texture file: {error:?}"),
+    }
+}
+
+fn power_up_style(kind: PowerUpKind) -> (Color, &'static str) {
+    match kind {
+        PowerUpKind::SpreadShot => (SKYBLUE, "3"),
+        PowerUpKind::RapidFire => (YELLOW, "R"),
+        PowerUpKind::BurstFire => (ORANGE, "B"),
+        PowerUpKind::Piercing => (MAGENTA, "P"),
+        PowerUpKind::Shield => (BLUE, "S"),
+        PowerUpKind::ExtraLife => (RED, "+"),
+    }
+}
+
+/// Scales the logical playfield to the biggest size that fits into the window and centers it.
+/// Everything drawn afterwards uses playfield coordinates.
+pub fn set_playfield_camera() {
+    let screen = vec2(screen_width(), screen_height());
+    let scale = (screen.x / PLAYFIELD.x).min(screen.y / PLAYFIELD.y);
+    let size = PLAYFIELD * scale;
+    let offset = (screen - size) / 2.0;
+    let dpi_scale = screen_dpi_scale();
+
+    let mut camera = Camera2D::from_display_rect(Rect::new(0.0, 0.0, PLAYFIELD.x, PLAYFIELD.y));
+    // from_display_rect is meant for render targets, drawing to the screen would be upside down
+    camera.zoom.y = -camera.zoom.y;
+    camera.viewport = Some((
+        (offset.x * dpi_scale) as i32,
+        (offset.y * dpi_scale) as i32,
+        (size.x * dpi_scale) as i32,
+        (size.y * dpi_scale) as i32,
+    ));
+    set_camera(&camera);
+}
+
+/// Draws a `World` to the screen. All textures are owned here, the simulation only
+/// refers to them by file name.
+pub struct Renderer {
+    textures: HashMap<String, Texture2D>,
+}
+
+impl Renderer {
+    /// Loads every PNG file of the assets directory, so level files can use any of them.
+    pub async fn load() -> Self {
+        let mut filenames: Vec<String> = fs::read_dir(ASSETS_DIRECTORY)
+            .unwrap_or_else(|error| panic!("Problem reading the assets directory: {error:?}"))
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .filter(|filename| filename.ends_with(".png"))
+            .collect();
+        filenames.sort();
+
+        let mut textures = HashMap::new();
+        for filename in filenames {
+            let texture = load_texture_from_file(&filename).await;
+            textures.insert(filename, texture);
+        }
+
+        Self { textures }
+    }
+
+    pub fn texture_sizes(&self) -> HashMap<String, Vec2> {
+        self.textures
+            .iter()
+            .map(|(name, texture)| (name.clone(), texture.size()))
+            .collect()
+    }
+
+    /// The collision masks of all textures, generated from their alpha channel
+    pub fn collision_masks(&self) -> HashMap<String, CollisionMask> {
+        self.textures
+            .iter()
+            .map(|(name, texture)| (name.clone(), CollisionMask::from_image(&texture.get_texture_data())))
+            .collect()
+    }
+
+    fn draw_sprite(&self, texture: &str, position: Vec2, size: Vec2) {
+        self.draw_tinted_sprite(texture, position, size, WHITE);
+    }
+
+    fn draw_tinted_sprite(&self, texture: &str, position: Vec2, size: Vec2, color: Color) {
+        draw_texture_ex(
+            &self.textures[texture],
+            position.x,
+            position.y,
+            color,
+            DrawTextureParams {
+                dest_size: Some(size),
+                ..Default::default()
+            },
+        );
+    }
+
+    /// `alpha` is the progress towards the next simulation tick. Every moving entity keeps its
+    /// `previous_position` from before the last tick and is drawn between it and its current
+    /// position accordingly, so the movement stays smooth at any frame rate.
+    pub fn draw_world(&self, world: &World, alpha: f32) {
+        // Let the player blink while it is invulnerable after losing a life
+        let player = &world.player;
+        if !player.is_invulnerable() || (player.invulnerable * 10.0) as i32 % 2 == 0 {
+            let position = player.previous_position.lerp(player.position, alpha);
+            self.draw_sprite(&player.texture, position, player.size());
+
+            if player.power_ups.is_active(PowerUpKind::Shield) {
+                let center = position + player.size() / 2.0;
+                draw_circle_lines(center.x, center.y, player.size().max_element() * 0.75, 4.0, BLUE);
+            }
+        }
+
+        for enemy in &world.enemies {
+            // The formation moves in discrete steps, so only diving enemies are interpolated
+            let mut position = match &enemy.dive {
+                Some(dive) => dive.previous_position.lerp(dive.position, alpha),
+                None => enemy.position(&world.formation),
+            };
+            let mut tint = WHITE;
+            // Armoured enemies shake and flash when they are hit and get redder the more damage they take
+            if enemy.hit_flash > 0.0 {
+                position.x += (enemy.hit_flash * 200.0).sin() * PADDING / 2.0;
+                tint = RED;
+            } else if enemy.hit_points < enemy.kind.hit_points {
+                let health = enemy.hit_points as f32 / enemy.kind.hit_points as f32;
+                tint = Color::new(1.0, 0.5 + health / 2.0, 0.5 + health / 2.0, 1.0);
+            }
+            self.draw_tinted_sprite(&enemy.kind.texture, position, enemy.size(), tint);
+        }
+
+        if let Some(boss) = &world.boss {
+            let position = boss.previous_position.lerp(boss.position, alpha);
+            let tint = if boss.hit_flash > 0.0 { RED } else { WHITE };
+            self.draw_tinted_sprite(&boss.texture, position, boss.size(), tint);
+
+            // Health bar at the top of the playfield
+            let bar_width = world.playfield.x / 2.0;
+            let bar_position = vec2(world.playfield.x / 2.0 - bar_width / 2.0, PADDING);
+            let health = boss.health as f32 / boss.max_health as f32;
+            draw_rectangle(bar_position.x, bar_position.y, bar_width * health, PADDING * 2.0, RED);
+            draw_rectangle_lines(bar_position.x, bar_position.y, bar_width, PADDING * 2.0, 2.0, LIGHTGRAY);
+        }
+
+        if let Some(ufo) = &world.ufo {
+            let center = ufo.previous_position.lerp(ufo.position, alpha) + Ufo::SIZE / 2.0;
+            draw_circle(center.x, center.y - Ufo::SIZE.y / 4.0, Ufo::SIZE.y / 2.5, SKYBLUE);
+            draw_ellipse(center.x, center.y + Ufo::SIZE.y / 8.0, Ufo::SIZE.x / 2.0, Ufo::SIZE.y / 3.0, 0.0, RED);
+        }
+
+        for bunker in &world.bunkers {
+            for cell in bunker.cells() {
+                draw_rectangle(cell.x, cell.y, bunker.cell_size, bunker.cell_size, GREEN);
+            }
+        }
+
+        for power_up in &world.power_ups {
+            let (color, symbol) = power_up_style(power_up.kind);
+            let position = power_up.previous_position.lerp(power_up.position, alpha);
+            draw_rectangle(position.x, position.y, PowerUp::SIZE.x, PowerUp::SIZE.y, color);
+            let symbol_size = measure_text(symbol, None, HUD_FONT_SIZE, 1.0);
+            draw_text(
+                symbol,
+                position.x + (PowerUp::SIZE.x - symbol_size.width) / 2.0,
+                position.y + (PowerUp::SIZE.y + symbol_size.height) / 2.0,
+                HUD_FONT_SIZE as f32,
+                BLACK,
+            );
+        }
+
+        for bullet in world.player_bullets.iter().chain(world.enemy_bullets.iter()) {
+            let color = match bullet.owner {
+                Owner::Player => WHITE,
+                Owner::Enemy => ORANGE,
+            };
+            let position = bullet.previous_position.lerp(bullet.position, alpha);
+            draw_circle(position.x, position.y, Bullet::radius(world.playfield), color);
+        }
+
+        for floating_text in &world.floating_texts {
+            let color = Color { a: floating_text.remaining / FloatingText::DURATION, ..YELLOW };
+            let text_size = measure_text(&floating_text.text, None, HUD_FONT_SIZE, 1.0);
+            draw_text(
+                &floating_text.text,
+                floating_text.position.x - text_size.width / 2.0,
+                floating_text.position.y,
+                HUD_FONT_SIZE as f32,
+                color,
+            );
+        }
+
+        self.draw_hud(world);
+    }
+
+    /// Shows the remaining lives as small player sprites in the bottom left corner,
+    /// the active power-ups in the bottom center and the current level in the bottom right corner.
+    fn draw_hud(&self, world: &World) {
+        let icon_size = world.player.size() / 2.0;
+        let y = world.playfield.y - icon_size.y - PADDING;
+        let text = "LIVES:";
+        let text_size = measure_text(text, None, HUD_FONT_SIZE, 1.0);
+        draw_text(text, PADDING, y + icon_size.y / 2.0 + text_size.height / 2.0, HUD_FONT_SIZE as f32, LIGHTGRAY);
+
+        for life in 0..world.player.lives {
+            let x = PADDING * 2.0 + text_size.width + life as f32 * (icon_size.x + PADDING);
+            self.draw_sprite(&world.player.texture, vec2(x, y), icon_size);
+        }
+
+        let multiplier = world.score.multiplier();
+        let power_ups_text = (multiplier > 1).then(|| format!("COMBO x{multiplier}"))
+            .into_iter()
+            .chain(world.player.power_ups.iter().map(|(kind, remaining)| format!("{} {:.1}s", kind.label(), remaining)))
+            .collect::<Vec<String>>()
+            .join("  ");
+        let power_ups_text_size = measure_text(&power_ups_text, None, HUD_FONT_SIZE, 1.0);
+        draw_text(
+            &power_ups_text,
+            world.playfield.x / 2.0 - power_ups_text_size.width / 2.0,
+            y + icon_size.y / 2.0 + power_ups_text_size.height / 2.0,
+            HUD_FONT_SIZE as f32,
+            LIGHTGRAY,
+        );
+
+        let boss_text = if world.is_boss_level() { " BOSS" } else { "" };
+        let level_text = format!("SCORE: {}  LEVEL {}: {}{}", world.score.total, world.level, world.current_level().name, boss_text);
+        let level_text_size = measure_text(&level_text, None, HUD_FONT_SIZE, 1.0);
+        draw_text(
+            &level_text,
+            world.playfield.x - level_text_size.width - PADDING,
+            y + icon_size.y / 2.0 + level_text_size.height / 2.0,
+            HUD_FONT_SIZE as f32,
+            LIGHTGRAY,
+        );
+    }
+}
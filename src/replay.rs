@@ -0,0 +1,210 @@
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::Path;
+use macroquad::prelude::*;
+use sha2::{Digest, Sha256};
+use crate::game_mode::Difficulty;
+use crate::input::Input;
+use crate::world::PLAYFIELD;
+
+const MAGIC: &[u8; 4] = b"CIRP";
+const VERSION: u8 = 3;
+/// Everything up to the game version, which has a length byte in front
+const HEADER_LENGTH: usize = 4 + 1 + 8 + 1 + 1 + 4 + 4 + 32 + 1;
+const GAME_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// A recorded game: everything needed to play it again tick by tick.
+///
+/// The file format is a small header (magic, version, seed, start level, difficulty,
+/// playfield size, hash of the levels and the version of the game) followed by the inputs,
+/// run-length encoded as pairs of an input bit mask and a little-endian `u16` repeat count.
+/// The playfield is always `PLAYFIELD`, it is only stored to reject files of other layouts.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Replay {
+    pub seed: u64,
+    pub level: i8,
+    pub difficulty: Difficulty,
+    /// See `World::levels_hash`
+    pub levels_hash: [u8; 32],
+    /// Version of the game which recorded the replay
+    pub game_version: String,
+    pub inputs: Vec<Input>,
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, message)
+}
+
+impl Replay {
+    pub const DIRECTORY: &'static str = "replays";
+    pub const EXTENSION: &'static str = "cirp";
+
+    pub fn new(seed: u64, level: i8, difficulty: Difficulty, levels_hash: [u8; 32]) -> Self {
+        Self { seed, level, difficulty, levels_hash, game_version: GAME_VERSION.to_string(), inputs: Vec::new() }
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Self::decode(&fs::read(path)?)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        fs::write(path, self.encode())
+    }
+
+    /// SHA-256 of the encoded replay as hex, identifies the game a high score was reached in
+    pub fn hash(&self) -> String {
+        hex::encode(Sha256::digest(self.encode()))
+    }
+
+    /// A replay only plays out like the recorded game with the same rules and levels, so it
+    /// must have been recorded by this version of the game with the levels of `levels_hash`
+    pub fn check_compatible(&self, levels_hash: &[u8; 32]) -> Result<(), String> {
+        if self.game_version != GAME_VERSION {
+            return Err(format!("it was recorded by version {} of the game, this is {}", self.game_version, GAME_VERSION));
+        }
+        if self.levels_hash != *levels_hash {
+            return Err("it was recorded with other levels, start the game with the --levels directory of the recording".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LENGTH + self.game_version.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        bytes.extend_from_slice(&self.level.to_le_bytes());
+        bytes.push(self.difficulty.index());
+        bytes.extend_from_slice(&PLAYFIELD.x.to_le_bytes());
+        bytes.extend_from_slice(&PLAYFIELD.y.to_le_bytes());
+        bytes.extend_from_slice(&self.levels_hash);
+        // Cargo versions are far shorter than 256 bytes
+        bytes.push(self.game_version.len() as u8);
+        bytes.extend_from_slice(self.game_version.as_bytes());
+
+        let mut inputs = self.inputs.iter().peekable();
+        while let Some(input) = inputs.next() {
+            let mut count: u16 = 1;
+            while count < u16::MAX && inputs.peek() == Some(&input) {
+                inputs.next();
+                count += 1;
+            }
+            bytes.push(input.to_bits());
+            bytes.extend_from_slice(&count.to_le_bytes());
+        }
+
+        bytes
+    }
+
+    pub fn decode(bytes: &[u8]) -> io::Result<Self> {
+        if bytes.len() < 5 || &bytes[0..4] != MAGIC {
+            return Err(invalid_data("not a CodeInvaders replay"));
+        }
+        if bytes[4] != VERSION {
+            return Err(invalid_data("unsupported replay version"));
+        }
+        if bytes.len() < HEADER_LENGTH {
+            return Err(invalid_data("truncated replay"));
+        }
+
+        // The slices have a fixed length, so the conversions can't fail
+        let seed = u64::from_le_bytes(bytes[5..13].try_into().unwrap());
+        let level = i8::from_le_bytes([bytes[13]]);
+        if level < 1 {
+            return Err(invalid_data("invalid start level in replay"));
+        }
+        let difficulty = Difficulty::from_index(bytes[14]).ok_or_else(|| invalid_data("invalid difficulty in replay"))?;
+        let playfield = vec2(
+            f32::from_le_bytes(bytes[15..19].try_into().unwrap()),
+            f32::from_le_bytes(bytes[19..23].try_into().unwrap()),
+        );
+        if playfield != PLAYFIELD {
+            return Err(invalid_data("replay was recorded on a different playfield"));
+        }
+        let levels_hash = bytes[23..55].try_into().unwrap();
+
+        let runs_start = HEADER_LENGTH + bytes[55] as usize;
+        let game_version = bytes
+            .get(HEADER_LENGTH..runs_start)
+            .ok_or_else(|| invalid_data("truncated replay"))?;
+        let game_version = String::from_utf8(game_version.to_vec()).map_err(|_| invalid_data("invalid game version in replay"))?;
+
+        let runs = &bytes[runs_start..];
+        if !runs.len().is_multiple_of(3) {
+            return Err(invalid_data("truncated replay"));
+        }
+
+        let mut inputs = Vec::new();
+        for run in runs.chunks_exact(3) {
+            let input = Input::from_bits(run[0]).ok_or_else(|| invalid_data("invalid input in replay"))?;
+            let count = u16::from_le_bytes([run[1], run[2]]);
+            inputs.extend(std::iter::repeat_n(input, count as usize));
+        }
+
+        Ok(Self { seed, level, difficulty, levels_hash, game_version, inputs })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(left: bool, right: bool, fire: bool) -> Input {
+        Input { left, right, fire, escape: false }
+    }
+
+    #[test]
+    fn encode_and_decode_round_trip() {
+        let mut replay = Replay::new(0x0123_4567_89ab_cdef, 3, Difficulty::Hard, [7; 32]);
+        // Longer than one run can count, so it is split in two
+        replay.inputs.extend(std::iter::repeat_n(input(false, false, true), u16::MAX as usize + 5));
+        replay.inputs.extend(std::iter::repeat_n(input(true, false, false), 3));
+        replay.inputs.push(input(false, true, true));
+
+        let bytes = replay.encode();
+        assert_eq!(bytes.len(), HEADER_LENGTH + GAME_VERSION.len() + 4 * 3);
+        assert_eq!(Replay::decode(&bytes).unwrap(), replay);
+    }
+
+    #[test]
+    fn truncated_replays_are_rejected() {
+        let mut replay = Replay::new(7, 1, Difficulty::Easy, [0; 32]);
+        replay.inputs.push(input(true, false, false));
+        let bytes = replay.encode();
+
+        let runs_start = HEADER_LENGTH + GAME_VERSION.len();
+        for length in [0, 4, 5, HEADER_LENGTH - 1, runs_start - 1, bytes.len() - 1] {
+            let error = Replay::decode(&bytes[..length]).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::InvalidData, "length {length}");
+        }
+        assert!(Replay::decode(&bytes[..runs_start]).unwrap().inputs.is_empty());
+    }
+
+    #[test]
+    fn invalid_headers_are_rejected() {
+        let bytes = Replay::new(7, 1, Difficulty::Easy, [0; 32]).encode();
+
+        for level in [0, -1, i8::MIN] {
+            let mut edited = bytes.clone();
+            edited[13] = level as u8;
+            assert_eq!(Replay::decode(&edited).unwrap_err().kind(), ErrorKind::InvalidData, "level {level}");
+        }
+
+        let mut edited = bytes.clone();
+        edited[15..19].copy_from_slice(&1280.0_f32.to_le_bytes());
+        assert_eq!(Replay::decode(&edited).unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn replays_of_other_levels_or_versions_are_incompatible() {
+        let mut replay = Replay::new(7, 1, Difficulty::Easy, [1; 32]);
+        assert!(replay.check_compatible(&[1; 32]).is_ok());
+        assert!(replay.check_compatible(&[2; 32]).is_err());
+
+        replay.game_version = "0.0.1".to_string();
+        assert!(replay.check_compatible(&[1; 32]).is_err());
+    }
+}
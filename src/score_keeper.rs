@@ -0,0 +1,150 @@
+/// Seconds after a kill in which the next kill continues the combo
+const COMBO_WINDOW: f32 = 2.0;
+/// Every this many chained kills the multiplier grows by one
+const KILLS_PER_MULTIPLIER: u32 = 5;
+const MAX_MULTIPLIER: u32 = 4;
+/// Points for hitting with every single bullet of a level
+const ACCURACY_BONUS: f32 = 500.0;
+/// Levels cleared faster than this give points for every second left
+const PAR_TIME: f32 = 90.0;
+const POINTS_PER_SECOND: f32 = 10.0;
+const NO_DAMAGE_BONUS: i32 = 250;
+
+/// The points of a single level by where they came from
+#[derive(Clone, Debug, Default)]
+pub struct LevelScore {
+    /// Points of the destroyed enemies without the combo bonus
+    pub enemies: i32,
+    /// Extra points for the combo multiplier
+    pub combo: i32,
+    /// Points for hitting and destroying the boss
+    pub boss: i32,
+    pub ufo: i32,
+    pub accuracy: i32,
+    pub time: i32,
+    pub no_damage: i32,
+}
+
+impl LevelScore {
+    pub fn total(&self) -> i32 {
+        self.enemies + self.combo + self.boss + self.ufo + self.accuracy + self.time + self.no_damage
+    }
+}
+
+/// Counts the points of a game. Points are only ever added, so the score can't get negative.
+///
+/// Destroying enemies in quick succession builds up a combo which multiplies their points,
+/// and at the end of a level accuracy, the time needed and not getting hit give bonuses.
+#[derive(Clone, Debug, Default)]
+pub struct ScoreKeeper {
+    /// Points of the whole game including the current level
+    pub total: i32,
+    /// Breakdown of the current level, complete once `finish_level` was called
+    pub level: LevelScore,
+    /// Bullets fired and bullets which hit something during the whole game
+    pub shots: u32,
+    pub hits: u32,
+    /// Kills in the current combo
+    pub combo: u32,
+    pub best_combo: u32,
+    /// Seconds the whole game and the current level have been played
+    pub play_time: f32,
+    pub level_time: f32,
+    level_shots: u32,
+    level_hits: u32,
+    combo_timer: f32,
+    damage_taken: bool,
+}
+
+impl ScoreKeeper {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts counting a new level, the total stays
+    pub fn start_level(&mut self) {
+        *self = Self {
+            total: self.total,
+            shots: self.shots,
+            hits: self.hits,
+            best_combo: self.best_combo,
+            play_time: self.play_time,
+            ..Self::default()
+        };
+    }
+
+    pub fn update(&mut self, delta_time: f32) {
+        self.play_time += delta_time;
+        self.level_time += delta_time;
+        self.combo_timer -= delta_time;
+        if self.combo_timer <= 0.0 {
+            self.combo = 0;
+        }
+    }
+
+    /// The share of the bullets fired in the current level which hit something
+    pub fn level_accuracy(&self) -> f32 {
+        if self.level_shots == 0 {
+            return 0.0;
+        }
+        self.level_hits as f32 / self.level_shots as f32
+    }
+
+    pub fn multiplier(&self) -> u32 {
+        (1 + self.combo / KILLS_PER_MULTIPLIER).min(MAX_MULTIPLIER)
+    }
+
+    fn add(&mut self, points: i32) {
+        self.total += points;
+    }
+
+    pub fn bullets_fired(&mut self, count: u32) {
+        self.shots += count;
+        self.level_shots += count;
+    }
+
+    /// Counts a bullet which hit something for the first time
+    pub fn bullet_hit(&mut self) {
+        self.hits += 1;
+        self.level_hits += 1;
+    }
+
+    /// Adds the points of a destroyed enemy with the combo multiplier and returns them
+    pub fn enemy_destroyed(&mut self, points: i32) -> i32 {
+        self.combo += 1;
+        self.best_combo = self.best_combo.max(self.combo);
+        self.combo_timer = COMBO_WINDOW;
+
+        let bonus = points * (self.multiplier() as i32 - 1);
+        self.level.enemies += points;
+        self.level.combo += bonus;
+        self.add(points + bonus);
+        points + bonus
+    }
+
+    pub fn boss_hit(&mut self, points: i32) {
+        self.level.boss += points;
+        self.add(points);
+    }
+
+    pub fn ufo_hit(&mut self, points: i32) {
+        self.level.ufo += points;
+        self.add(points);
+    }
+
+    pub fn player_hit(&mut self) {
+        self.damage_taken = true;
+        self.combo = 0;
+    }
+
+    /// Adds the bonuses for the completed level
+    pub fn finish_level(&mut self) {
+        self.level.accuracy = (self.level_accuracy() * ACCURACY_BONUS).round() as i32;
+        self.level.time = ((PAR_TIME - self.level_time).max(0.0) * POINTS_PER_SECOND).round() as i32;
+        if !self.damage_taken {
+            self.level.no_damage = NO_DAMAGE_BONUS;
+        }
+
+        self.add(self.level.accuracy + self.level.time + self.level.no_damage);
+    }
+}
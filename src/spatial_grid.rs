@@ -0,0 +1,69 @@
+use macroquad::prelude::*;
+
+/// A uniform grid over the playfield used as the broad phase of the collision detection.
+///
+/// Every entity is inserted with its bounding box into all cells it overlaps, a query then
+/// only has to look at the entities of the cells its own box overlaps instead of all of them.
+/// Boxes outside of the area are clamped to the border cells, so nothing gets lost.
+pub struct SpatialGrid {
+    cell_size: f32,
+    columns: usize,
+    rows: usize,
+    cells: Vec<Vec<usize>>,
+}
+
+impl SpatialGrid {
+    pub fn new(area: Vec2, cell_size: f32) -> Self {
+        let columns = (area.x / cell_size).ceil().max(1.0) as usize;
+        let rows = (area.y / cell_size).ceil().max(1.0) as usize;
+
+        Self {
+            cell_size,
+            columns,
+            rows,
+            cells: vec![Vec::new(); columns * rows],
+        }
+    }
+
+    /// Removes all entities but keeps the allocated cells for the next tick
+    pub fn clear(&mut self) {
+        for cell in self.cells.iter_mut() {
+            cell.clear();
+        }
+    }
+
+    /// The range of columns and rows the box overlaps
+    fn cell_range(&self, position: Vec2, size: Vec2) -> ((usize, usize), (usize, usize)) {
+        let to_cell = |value: f32, count: usize| ((value / self.cell_size).floor().max(0.0) as usize).min(count - 1);
+
+        (
+            (to_cell(position.x, self.columns), to_cell(position.x + size.x, self.columns)),
+            (to_cell(position.y, self.rows), to_cell(position.y + size.y, self.rows)),
+        )
+    }
+
+    pub fn insert(&mut self, index: usize, position: Vec2, size: Vec2) {
+        let ((first_column, last_column), (first_row, last_row)) = self.cell_range(position, size);
+        for row in first_row..=last_row {
+            for column in first_column..=last_column {
+                self.cells[row * self.columns + column].push(index);
+            }
+        }
+    }
+
+    /// Fills `candidates` with the indices of all entities which might overlap the box,
+    /// sorted in ascending order and without duplicates.
+    pub fn query(&self, position: Vec2, size: Vec2, candidates: &mut Vec<usize>) {
+        candidates.clear();
+
+        let ((first_column, last_column), (first_row, last_row)) = self.cell_range(position, size);
+        for row in first_row..=last_row {
+            for column in first_column..=last_column {
+                candidates.extend_from_slice(&self.cells[row * self.columns + column]);
+            }
+        }
+
+        candidates.sort_unstable();
+        candidates.dedup();
+    }
+}
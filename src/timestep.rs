@@ -0,0 +1,73 @@
+/// Turns variable frame times into a whole number of fixed simulation ticks, so the
+/// simulation behaves the same no matter how fast the machine renders.
+pub struct FixedTimestep {
+    accumulator: f32,
+}
+
+impl Default for FixedTimestep {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FixedTimestep {
+    /// Duration of one simulation tick (120 Hz)
+    pub const TICK: f32 = 1.0 / 120.0;
+    /// Longer frames are cut to this, otherwise a single hitch would need hundreds of ticks to catch up
+    const MAX_FRAME_TIME: f32 = 0.25;
+    /// Fraction of a tick up to which the accumulated time counts as a whole tick
+    const ROUNDING_TOLERANCE: f32 = 1e-3;
+
+    pub fn new() -> Self {
+        Self { accumulator: 0.0 }
+    }
+
+    /// Adds the time of the last frame and returns how many ticks have to be simulated now.
+    pub fn advance(&mut self, frame_time: f32) -> u32 {
+        self.accumulator += frame_time.min(Self::MAX_FRAME_TIME);
+
+        // Frames which are a whole number of ticks long must not lose one to rounding
+        let ticks = (self.accumulator / Self::TICK + Self::ROUNDING_TOLERANCE) as u32;
+        self.accumulator = (self.accumulator - ticks as f32 * Self::TICK).max(0.0);
+        ticks
+    }
+
+    /// How far the time already is between the last and the next tick (0 to 1).
+    /// Used to interpolate the positions while rendering.
+    pub fn alpha(&self) -> f32 {
+        self.accumulator / Self::TICK
+    }
+
+    pub fn reset(&mut self) {
+        self.accumulator = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn advance_returns_whole_ticks_and_keeps_the_rest() {
+        let mut timestep = FixedTimestep::new();
+        assert_eq!(timestep.advance(FixedTimestep::TICK * 0.5), 0);
+        assert!((timestep.alpha() - 0.5).abs() < 1e-3);
+
+        assert_eq!(timestep.advance(FixedTimestep::TICK * 2.0), 2);
+        assert!((timestep.alpha() - 0.5).abs() < 1e-3);
+
+        // The leftover halves add up to another tick
+        assert_eq!(timestep.advance(FixedTimestep::TICK * 0.5), 1);
+        assert!(timestep.alpha() < 1e-3);
+    }
+
+    #[test]
+    fn long_frames_are_clamped() {
+        let mut timestep = FixedTimestep::new();
+        let ticks = timestep.advance(10.0);
+        assert_eq!(ticks, (FixedTimestep::MAX_FRAME_TIME / FixedTimestep::TICK).round() as u32);
+
+        timestep.reset();
+        assert_eq!(timestep.alpha(), 0.0);
+    }
+}
@@ -0,0 +1,48 @@
+use macroquad::prelude::*;
+
+/// The mystery ship which now and then crosses the top of the playfield above the formation.
+pub struct Ufo {
+    pub position: Vec2,
+    pub previous_position: Vec2,
+    pub moving_right: bool,
+    pub collided: bool,
+}
+
+impl Ufo {
+    pub const SIZE: Vec2 = Vec2::new(96.0, 40.0);
+    /// The possible scores, one of them is picked at random when the UFO is hit
+    pub const SCORES: [i32; 4] = [50, 100, 150, 300];
+
+    /// Creates a UFO just outside of the left or right edge of the playfield
+    pub fn new(moving_right: bool, y: f32, playfield: Vec2) -> Self {
+        let x = if moving_right { -Self::SIZE.x } else { playfield.x };
+        let position = vec2(x, y);
+
+        Self {
+            position,
+            previous_position: position,
+            moving_right,
+            collided: false,
+        }
+    }
+
+    pub fn update(&mut self, delta_time: f32, playfield: Vec2) {
+        self.previous_position = self.position;
+
+        let move_speed = playfield.x / 8.0;
+        if self.moving_right {
+            self.position.x += move_speed * delta_time;
+        } else {
+            self.position.x -= move_speed * delta_time;
+        }
+    }
+
+    /// `true` once the UFO has left the playfield on the other side
+    pub fn is_gone(&self, playfield: Vec2) -> bool {
+        if self.moving_right {
+            self.position.x > playfield.x
+        } else {
+            self.position.x < -Self::SIZE.x
+        }
+    }
+}
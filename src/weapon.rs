@@ -0,0 +1,86 @@
+/// How the weapon fires while the trigger is held
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FireMode {
+    /// One shot every cooldown
+    Auto,
+    /// `shots` shots `interval` seconds apart, then the cooldown
+    Burst { shots: u32, interval: f32 },
+}
+
+/// The weapon of the player. All times are in seconds, so the fire rate only depends on the
+/// simulated time and not on how often the simulation is stepped.
+pub struct Weapon {
+    /// Seconds between two shots, or between two bursts
+    pub cooldown: f32,
+    /// Multiplied with the cooldown, power-ups lower it to shoot faster
+    pub cooldown_multiplier: f32,
+    pub mode: FireMode,
+    /// Bullets of the player which may be on screen at once
+    pub max_bullets: usize,
+    /// Seconds until the next shot is possible, may be slightly negative to carry over
+    /// the part of a tick by which the last shot was late
+    timer: f32,
+    /// Shots left in the current burst
+    burst_remaining: u32,
+}
+
+impl Default for Weapon {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Weapon {
+    pub const COOLDOWN: f32 = 0.4;
+    pub const MAX_BULLETS: usize = 12;
+
+    pub fn new() -> Self {
+        Self {
+            cooldown: Self::COOLDOWN,
+            cooldown_multiplier: 1.0,
+            mode: FireMode::Auto,
+            max_bullets: Self::MAX_BULLETS,
+            timer: 0.0,
+            burst_remaining: 0,
+        }
+    }
+
+    /// Makes the weapon ready to fire immediately
+    pub fn reset(&mut self) {
+        self.timer = 0.0;
+        self.burst_remaining = 0;
+    }
+
+    /// Advances the weapon by `delta_time` seconds and returns `true` if it fires a shot.
+    /// While `can_fire` is `false` (e.g. too many bullets on screen) the shot is held back.
+    pub fn update(&mut self, trigger: bool, delta_time: f32, can_fire: bool) -> bool {
+        self.timer -= delta_time;
+        if self.timer > 0.0 || !can_fire {
+            return false;
+        }
+        // An idle weapon doesn't save up shots
+        let late = self.timer.max(-delta_time);
+
+        let interval = match self.mode {
+            FireMode::Auto => {
+                if !trigger {
+                    return false;
+                }
+                self.cooldown * self.cooldown_multiplier
+            }
+            FireMode::Burst { shots, interval } => {
+                if self.burst_remaining == 0 {
+                    if !trigger {
+                        return false;
+                    }
+                    self.burst_remaining = shots;
+                }
+                self.burst_remaining -= 1;
+                if self.burst_remaining > 0 { interval } else { self.cooldown * self.cooldown_multiplier }
+            }
+        };
+
+        self.timer = late + interval;
+        true
+    }
+}
@@ -0,0 +1,861 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+use macroquad::prelude::*;
+use macroquad::rand::RandGenerator;
+use sha2::{Digest, Sha256};
+use crate::boss::Boss;
+use crate::bullet::{Bullet, Owner};
+use crate::bunker::Bunker;
+use crate::collision_mask::CollisionMask;
+use crate::dive::Dive;
+use crate::enemy::{Enemy, EnemyKind, FireBehaviour};
+use crate::floating_text::FloatingText;
+use crate::formation::Formation;
+use crate::game_mode::Difficulty;
+use crate::input::Input;
+use crate::level::Level;
+use crate::player::Player;
+use crate::pool::Pool;
+use crate::power_up::{PowerUp, PowerUpKind};
+use crate::score_keeper::ScoreKeeper;
+use crate::spatial_grid::SpatialGrid;
+use crate::ufo::Ufo;
+use crate::weapon::FireMode;
+
+pub const PADDING: f32 = 10.0;
+/// Size of the logical playfield. The renderer scales it to the window, so the window
+/// size never changes the gameplay.
+pub const PLAYFIELD: Vec2 = Vec2::new(1920.0, 1080.0);
+pub const PLAYER_TEXTURE: &str = "rust.png";
+const BUNKER_COUNT: usize = 4;
+/// Chance that a destroyed enemy drops a power-up
+const POWER_UP_DROP_CHANCE: f32 = 0.08;
+const MAX_LIVES: u8 = 9;
+/// Health of the first boss, every following one has this much more
+const BOSS_BASE_HEALTH: u32 = 40;
+const BOSS_BONUS: i32 = 500;
+const BOSS_HIT_POINTS: i32 = 5;
+/// Range of seconds between two appearances of the UFO
+const UFO_INTERVAL: (f32, f32) = (15.0, 30.0);
+/// Whether the player and the boss can only be hit on the opaque pixels of their textures,
+/// enemies define this per type in the level files
+const PLAYER_PIXEL_COLLISION: bool = true;
+const BOSS_PIXEL_COLLISION: bool = true;
+/// Slots of the bullet pools, allocated once when the world is created
+const PLAYER_BULLET_CAPACITY: usize = 64;
+const ENEMY_BULLET_CAPACITY: usize = 256;
+/// Enemies which may be on a dive at the same time
+const MAX_DIVERS: usize = 3;
+/// The UFO flies in its own lane above the formation
+const UFO_LANE_Y: f32 = PADDING;
+const FORMATION_TOP: f32 = UFO_LANE_Y + Ufo::SIZE.y + PADDING;
+
+/// What happened during a single call to `World::step`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StepOutcome {
+    Running,
+    LevelComplete,
+    PlayerDefeated,
+}
+
+/// The complete game simulation. It knows nothing about windows, keyboards or textures,
+/// everything is measured in units of the logical playfield.
+pub struct World {
+    pub playfield: Vec2,
+    pub player: Player,
+    pub enemies: Vec<Enemy>,
+    pub formation: Formation,
+    pub player_bullets: Pool<Bullet>,
+    pub enemy_bullets: Pool<Bullet>,
+    pub bunkers: Vec<Bunker>,
+    pub power_ups: Vec<PowerUp>,
+    pub boss: Option<Boss>,
+    pub ufo: Option<Ufo>,
+    pub floating_texts: Vec<FloatingText>,
+    pub score: ScoreKeeper,
+    pub level: i8,
+    /// Difficulty of the current game, also the one the next game starts with
+    pub difficulty: Difficulty,
+    texture_sizes: HashMap<String, Vec2>,
+    collision_masks: HashMap<String, CollisionMask>,
+    levels: Vec<Level>,
+    rng: RandGenerator,
+    enemy_fire_rate: f32,
+    enemy_fire_timer: f32,
+    dive_timer: f32,
+    ufo_timer: f32,
+    /// Broad phase for the bullet/enemy collisions, rebuilt every tick
+    enemy_grid: SpatialGrid,
+    /// Reused buffer for the results of `enemy_grid` queries
+    collision_candidates: Vec<usize>,
+}
+
+/// Where on its way during the last tick the bullet first hit the sprite. The rectangle is
+/// checked first, the mask only refines a hit if there is one.
+fn bullet_impact(bullet: &Bullet, playfield: Vec2, position: Vec2, size: Vec2, mask: Option<&CollisionMask>) -> Option<f32> {
+    let impact = bullet.impact(playfield, position, size)?;
+    match mask {
+        Some(mask) => mask.swept_circle_hit(bullet.previous_position, bullet.position, Bullet::radius(playfield), position, size),
+        None => Some(impact),
+    }
+}
+
+/// Counts the first hit of a bullet for the accuracy
+fn count_hit(score: &mut ScoreKeeper, bullet: &mut Bullet) {
+    if !bullet.has_hit {
+        bullet.has_hit = true;
+        score.bullet_hit();
+    }
+}
+
+impl World {
+    /// `texture_sizes` maps every texture name to its size in pixels, which is needed to
+    /// give the entities the same proportions as their sprites. `levels` must not be empty,
+    /// they start over with increasing speed once all levels are played.
+    /// `collision_masks` are used for pixel-perfect collisions, textures without a mask
+    /// are hit on their whole rectangle.
+    pub fn new(
+        playfield: Vec2,
+        texture_sizes: HashMap<String, Vec2>,
+        collision_masks: HashMap<String, CollisionMask>,
+        levels: Vec<Level>,
+    ) -> Self {
+        let player = Player::new(PLAYER_TEXTURE, texture_sizes[PLAYER_TEXTURE], playfield);
+
+        Self {
+            playfield,
+            player,
+            enemies: Vec::new(),
+            formation: Formation::new(Vec2::ZERO, Vec2::ZERO, 0.0, 1.0, 0, playfield),
+            player_bullets: Pool::new(PLAYER_BULLET_CAPACITY),
+            enemy_bullets: Pool::new(ENEMY_BULLET_CAPACITY),
+            bunkers: Vec::new(),
+            power_ups: Vec::new(),
+            boss: None,
+            ufo: None,
+            floating_texts: Vec::new(),
+            score: ScoreKeeper::new(),
+            level: 0,
+            difficulty: Difficulty::Normal,
+            texture_sizes,
+            collision_masks,
+            levels,
+            rng: RandGenerator::new(),
+            enemy_fire_rate: 0.0,
+            enemy_fire_timer: 0.0,
+            dive_timer: 0.0,
+            ufo_timer: 0.0,
+            enemy_grid: SpatialGrid::new(playfield, playfield.x / 16.0),
+            collision_candidates: Vec::new(),
+        }
+    }
+
+    /// Resets level, score and lives, so the next call to `next_level` starts a new game.
+    pub fn reset(&mut self) {
+        self.level = 0;
+        self.score = ScoreKeeper::new();
+        self.player.lives = Player::START_LIVES;
+    }
+
+    /// Starts a new game at `level`. Two games started with the same `seed`, difficulty and
+    /// playfield play out exactly the same when they get the same inputs.
+    pub fn new_game(&mut self, seed: u64, level: i8, difficulty: Difficulty) {
+        self.reset();
+        self.difficulty = difficulty;
+        self.rng.srand(seed);
+        self.player.invulnerable = 0.0;
+        self.player.power_ups.clear();
+
+        self.level = level.max(1) - 1;
+        self.next_level();
+    }
+
+    /// The definition of the level which is currently played. Once all levels are played
+    /// they start over.
+    pub fn current_level(&self) -> &Level {
+        &self.levels[self.level_index()]
+    }
+
+    fn level_index(&self) -> usize {
+        (self.level.max(1) as usize - 1) % self.levels.len()
+    }
+
+    /// How often all levels were played already
+    fn round(&self) -> usize {
+        (self.level.max(1) as usize - 1) / self.levels.len()
+    }
+
+    /// SHA-256 over the hashes of all level files, replays are only played with the levels
+    /// they were recorded with
+    pub fn levels_hash(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        for level in &self.levels {
+            hasher.update(level.file_hash);
+        }
+        hasher.finalize().into()
+    }
+
+    pub fn is_boss_level(&self) -> bool {
+        self.current_level().boss
+    }
+
+    /// Counts the boss levels up to the current one, every boss is stronger than the last
+    fn boss_number(&self) -> u32 {
+        let bosses_per_round = self.levels.iter().filter(|level| level.boss).count();
+        let bosses_this_round = self.levels[..=self.level_index()].iter().filter(|level| level.boss).count();
+        (self.round() * bosses_per_round + bosses_this_round) as u32
+    }
+
+    /// Speed of the enemies in the current round compared to the first one. Every round
+    /// continues where the last one stopped: its first level is 1.5 times faster than the last
+    /// level of the previous round.
+    fn round_speed_multiplier(&self) -> f32 {
+        let mut formations = self.levels.iter().filter(|level| !level.boss);
+        let (Some(first), Some(last)) = (formations.next(), formations.next_back()) else {
+            return 1.5_f32.powi(self.round() as i32);
+        };
+        (last.speed_multiplier / first.speed_multiplier * 1.5).powi(self.round() as i32)
+    }
+
+    /// Starts the next level. The levels start over forever, but the counter stops at the
+    /// highest level it can show.
+    pub fn next_level(&mut self) {
+        self.level = self.level.saturating_add(1);
+
+        self.player.reset(self.playfield);
+
+        self.player_bullets.clear();
+        self.enemy_bullets.clear();
+
+        self.enemies.clear();
+
+        self.power_ups.clear();
+
+        self.score.start_level();
+
+        self.ufo = None;
+        self.ufo_timer = self.rng.gen_range(UFO_INTERVAL.0, UFO_INTERVAL.1);
+        self.floating_texts.clear();
+
+        let level = self.current_level().clone();
+        let difficulty = self.difficulty.enemy_multiplier();
+        let speed_multiplier = level.speed_multiplier * self.round_speed_multiplier() * difficulty;
+
+        self.enemy_fire_rate = level.fire_rate * difficulty;
+        self.enemy_fire_timer = self.next_enemy_fire_interval();
+        self.dive_timer = self.next_dive_interval();
+
+        if level.boss {
+            let texture_size = self.texture_sizes[&level.texture];
+            let health = BOSS_BASE_HEALTH * self.boss_number();
+            self.boss = Some(Boss::new(&level.texture, texture_size, health, self.playfield));
+        } else {
+            self.boss = None;
+            self.spawn_enemies(&level, speed_multiplier);
+        }
+
+        self.spawn_bunkers();
+    }
+
+    fn spawn_enemies(&mut self, level: &Level, speed_multiplier: f32) {
+        let mut kinds: HashMap<char, Rc<EnemyKind>> = HashMap::new();
+        for (_, _, symbol) in level.cells() {
+            if let Some(kind) = level.kind(symbol) {
+                kinds.entry(symbol).or_insert_with(|| Rc::new(kind));
+            }
+        }
+
+        let enemy_width = self.playfield.x / 20.0;
+        let row_height = kinds
+            .values()
+            .map(|kind| self.texture_sizes[&kind.texture])
+            .map(|texture_size| texture_size.y * enemy_width / texture_size.x)
+            .fold(0.0, f32::max);
+        let spacing = vec2(enemy_width + PADDING, row_height + PADDING);
+
+        for (col, row, symbol) in level.cells() {
+            let kind = &kinds[&symbol];
+            let texture_size = self.texture_sizes[&kind.texture];
+
+            self.enemies.push(Enemy::new(
+                Rc::clone(kind),
+                texture_size,
+                enemy_width / texture_size.x,
+                (col, row),
+            ));
+        }
+
+        let speed = self.playfield.x / 25.0 * speed_multiplier;
+        let origin = vec2(PADDING + 1.0, FORMATION_TOP);
+        self.formation = Formation::new(origin, spacing, enemy_width, speed, self.enemies.len(), self.playfield);
+    }
+
+    /// Places the bunkers evenly spaced in a row above the player.
+    fn spawn_bunkers(&mut self) {
+        self.bunkers.clear();
+
+        let bunker_width = self.playfield.x / 12.0;
+        let gap = (self.playfield.x - BUNKER_COUNT as f32 * bunker_width) / (BUNKER_COUNT + 1) as f32;
+
+        for index in 0..BUNKER_COUNT {
+            let mut bunker = Bunker::new(Vec2::ZERO, bunker_width);
+            bunker.position = vec2(
+                gap + index as f32 * (bunker_width + gap),
+                self.player.position.y - bunker.size().y - PADDING * 4.0,
+            );
+            self.bunkers.push(bunker);
+        }
+    }
+
+    /// Advances the simulation by `delta_time` seconds. To get the same results on every
+    /// machine this should always be called with `FixedTimestep::TICK`.
+    pub fn step(&mut self, input: &Input, delta_time: f32) -> StepOutcome {
+        self.score.update(delta_time);
+
+        self.player.update(input, delta_time, self.playfield);
+
+        self.calculate_enemy_movement(delta_time);
+
+        self.start_dive(delta_time);
+
+        self.shoot_bullet(input, delta_time);
+
+        self.shoot_enemy_bullet(delta_time);
+
+        self.update_boss(delta_time);
+
+        self.update_ufo(delta_time);
+
+        for floating_text in self.floating_texts.iter_mut() {
+            floating_text.update(delta_time);
+        }
+        self.floating_texts.retain(|floating_text| floating_text.remaining > 0.0);
+
+        let playfield = self.playfield;
+        for bullets in [&mut self.player_bullets, &mut self.enemy_bullets] {
+            for (_, bullet) in bullets.iter_mut() {
+                bullet.update(delta_time, playfield);
+            }
+            bullets.retain(|bullet| {
+                bullet.position.y >= 0.0 && bullet.position.y < playfield.y &&
+                bullet.position.x >= 0.0 && bullet.position.x < playfield.x
+            });
+        }
+
+        for power_up in self.power_ups.iter_mut() {
+            power_up.update(delta_time, self.playfield);
+        }
+        self.power_ups.retain(|power_up| power_up.position.y < playfield.y);
+
+        self.check_collision();
+
+        self.check_player_hit();
+
+        self.collect_power_ups();
+
+        let outcome = self.check_round_finished();
+        if outcome == StepOutcome::LevelComplete {
+            self.score.finish_level();
+        }
+        outcome
+    }
+
+    fn calculate_enemy_movement(&mut self, delta_time: f32) {
+        self.formation.update(&self.enemies, delta_time, PADDING, self.playfield.x - PADDING);
+
+        for enemy in self.enemies.iter_mut() {
+            enemy.update(delta_time, &self.formation, self.playfield);
+        }
+    }
+
+    fn next_dive_interval(&self) -> f32 {
+        let level = self.current_level();
+        if level.dive_rate <= 0.0 || level.dive_patterns.is_empty() {
+            return f32::INFINITY;
+        }
+        self.rng.gen_range(0.5, 1.5) / (level.dive_rate * self.difficulty.enemy_multiplier())
+    }
+
+    /// Sends a random enemy of the formation on one of the dive patterns of the level.
+    fn start_dive(&mut self, delta_time: f32) {
+        self.dive_timer -= delta_time;
+        if self.dive_timer > 0.0 {
+            return;
+        }
+        self.dive_timer = self.next_dive_interval();
+
+        let candidates: Vec<usize> = (0..self.enemies.len()).filter(|&index| !self.enemies[index].is_diving()).collect();
+        if candidates.is_empty() || self.enemies.len() - candidates.len() >= MAX_DIVERS {
+            return;
+        }
+
+        let patterns = &self.current_level().dive_patterns;
+        let pattern = patterns[self.rng.gen_range(0, patterns.len())];
+        let side = if self.rng.gen_range(0, 2) == 0 { -1.0 } else { 1.0 };
+        let enemy = &mut self.enemies[candidates[self.rng.gen_range(0, candidates.len())]];
+        let start = enemy.position(&self.formation);
+        let target = self.player.position + (self.player.size() - enemy.size()) / 2.0;
+        enemy.dive = Some(Dive::new(pattern, start, target, side, self.playfield));
+    }
+
+    /// Lets the weapon of the player fire. Power-ups upgrade the weapon while they are active
+    /// and a shot waits until the pool has room for all its bullets.
+    fn shoot_bullet(&mut self, input: &Input, delta_time: f32) {
+        let power_ups = &self.player.power_ups;
+        let weapon = &mut self.player.weapon;
+        weapon.cooldown_multiplier = if power_ups.is_active(PowerUpKind::RapidFire) { 1.0 / 3.0 } else { 1.0 };
+        weapon.mode = if power_ups.is_active(PowerUpKind::BurstFire) {
+            FireMode::Burst { shots: 3, interval: 0.08 }
+        } else {
+            FireMode::Auto
+        };
+        self.player_bullets.set_max_active(weapon.max_bullets);
+
+        let spread = [Vec2::NEG_Y, Vec2::from_angle(-0.25).rotate(Vec2::NEG_Y), Vec2::from_angle(0.25).rotate(Vec2::NEG_Y)];
+        let directions = if power_ups.is_active(PowerUpKind::SpreadShot) { &spread[..] } else { &spread[..1] };
+        let can_fire = self.player_bullets.available() >= directions.len();
+        if !weapon.update(input.fire, delta_time, can_fire) {
+            return;
+        }
+
+        let bullet_position = vec2(
+            self.player.position.x + self.player.size().x / 2.0,
+            self.player.position.y
+        );
+        let piercing = power_ups.is_active(PowerUpKind::Piercing);
+        for &direction in directions {
+            let mut bullet = Bullet::new(bullet_position, Owner::Player);
+            bullet.direction = direction;
+            bullet.piercing = piercing;
+            self.player_bullets.insert(bullet);
+        }
+        self.score.bullets_fired(directions.len() as u32);
+    }
+
+    fn next_enemy_fire_interval(&self) -> f32 {
+        if self.enemy_fire_rate <= 0.0 {
+            return f32::INFINITY;
+        }
+        self.rng.gen_range(0.5, 1.5) / self.enemy_fire_rate
+    }
+
+    /// Lets a random enemy of the lowest row in its column shoot downwards.
+    fn shoot_enemy_bullet(&mut self, delta_time: f32) {
+        self.enemy_fire_timer -= delta_time;
+        if self.enemy_fire_timer > 0.0 || self.enemies.is_empty() {
+            return;
+        }
+        self.enemy_fire_timer = self.next_enemy_fire_interval();
+
+        // Only enemies in the formation without another enemy below them are allowed to shoot
+        let shooters: Vec<&Enemy> = self.enemies.iter().filter(|enemy| {
+            enemy.kind.fire != FireBehaviour::None && !enemy.is_diving() &&
+            !self.enemies.iter().any(|other| {
+                !other.is_diving() && other.slot.0 == enemy.slot.0 && other.slot.1 > enemy.slot.1
+            })
+        }).collect();
+        if shooters.is_empty() {
+            return;
+        }
+
+        let shooter = shooters[self.rng.gen_range(0, shooters.len())];
+        let shooter_position = shooter.position(&self.formation);
+        let bullet_position = vec2(
+            shooter_position.x + shooter.size().x / 2.0,
+            shooter_position.y + shooter.size().y
+        );
+        let mut bullet = Bullet::new(bullet_position, Owner::Enemy);
+        if shooter.kind.fire == FireBehaviour::Aimed {
+            let target = self.player.position + self.player.size() / 2.0;
+            bullet.direction = (target - bullet_position).try_normalize().unwrap_or(Vec2::Y);
+        }
+        self.enemy_bullets.insert(bullet);
+    }
+
+    fn update_boss(&mut self, delta_time: f32) {
+        let Some(boss) = self.boss.as_mut() else {
+            return;
+        };
+
+        let target = self.player.position + self.player.size() / 2.0;
+        for bullet in boss.update(delta_time, self.playfield, target) {
+            self.enemy_bullets.insert(bullet);
+        }
+    }
+
+    /// Spawns the UFO on its own timer, independent of the formation. There is no UFO on boss levels.
+    fn update_ufo(&mut self, delta_time: f32) {
+        if let Some(ufo) = self.ufo.as_mut() {
+            ufo.update(delta_time, self.playfield);
+            if ufo.is_gone(self.playfield) {
+                self.ufo = None;
+            }
+            return;
+        }
+
+        if self.boss.is_some() {
+            return;
+        }
+
+        self.ufo_timer -= delta_time;
+        if self.ufo_timer <= 0.0 {
+            self.ufo_timer = self.rng.gen_range(UFO_INTERVAL.0, UFO_INTERVAL.1);
+            let moving_right = self.rng.gen_range(0, 2) == 0;
+            self.ufo = Some(Ufo::new(moving_right, UFO_LANE_Y, self.playfield));
+        }
+    }
+
+    fn check_collision(&mut self) {
+        let radius = Bullet::radius(self.playfield);
+        for (_, bullet) in self.player_bullets.iter_mut().chain(self.enemy_bullets.iter_mut()) {
+            if self.bunkers.iter_mut().any(|bunker| bunker.hit(bullet.previous_position, bullet.position, radius)) {
+                bullet.collided = true;
+            }
+        }
+
+        // Enemies marching through a bunker destroy it
+        for enemy in &self.enemies {
+            for bunker in self.bunkers.iter_mut() {
+                bunker.erode(enemy.position(&self.formation), enemy.size());
+            }
+        }
+
+        self.enemy_grid.clear();
+        for (index, enemy) in self.enemies.iter().enumerate() {
+            self.enemy_grid.insert(index, enemy.position(&self.formation), enemy.size());
+        }
+
+        for (handle, bullet) in self.player_bullets.iter_mut().filter(|(_, bullet)| !bullet.collided) {
+            let (bounds_position, bounds_size) = bullet.swept_bounds(self.playfield);
+            self.enemy_grid.query(bounds_position, bounds_size, &mut self.collision_candidates);
+
+            let mut impacts: Vec<(f32, usize)> = self.collision_candidates
+                .iter()
+                .filter(|&&index| !self.enemies[index].collided && !self.enemies[index].pierced_by.contains(&handle))
+                .filter_map(|&index| {
+                    let enemy = &self.enemies[index];
+                    let mask = self.collision_masks.get(&enemy.kind.texture).filter(|_| enemy.kind.pixel_collision);
+                    bullet_impact(bullet, self.playfield, enemy.position(&self.formation), enemy.size(), mask).map(|t| (t, index))
+                })
+                .collect();
+            impacts.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+            // A normal bullet is used up by the first enemy on its way
+            if !bullet.piercing {
+                impacts.truncate(1);
+                bullet.collided = !impacts.is_empty();
+            }
+            if !impacts.is_empty() {
+                count_hit(&mut self.score, bullet);
+            }
+            for (_, index) in impacts {
+                let enemy = &mut self.enemies[index];
+                // A piercing bullet stays inside the enemy for a few ticks but only hits it once
+                enemy.pierced_by.push(handle);
+                if enemy.hit() {
+                    let multiplier = self.score.multiplier();
+                    let points = self.score.enemy_destroyed(enemy.kind.score);
+                    if multiplier > 1 {
+                        self.floating_texts.push(FloatingText::new(format!("+{points}"), bullet.position));
+                    }
+                }
+            }
+        }
+        self.check_boss_hit();
+
+        self.check_ufo_hit();
+
+        self.player_bullets.retain(|bullet| !bullet.collided);
+        self.enemy_bullets.retain(|bullet| !bullet.collided);
+
+        // Forget the piercing bullets which are gone, their slots get reused
+        for enemy in self.enemies.iter_mut() {
+            enemy.pierced_by.retain(|&handle| self.player_bullets.contains(handle));
+        }
+
+        for enemy in self.enemies.iter().filter(|enemy| enemy.collided) {
+            if self.rng.gen_range(0.0, 1.0) < POWER_UP_DROP_CHANCE {
+                let kind = PowerUpKind::ALL[self.rng.gen_range(0, PowerUpKind::ALL.len())];
+                let position = enemy.position(&self.formation) + (enemy.size() - PowerUp::SIZE) / 2.0;
+                self.power_ups.push(PowerUp::new(kind, position));
+            }
+        }
+        self.enemies.retain(|enemy| !enemy.collided);
+    }
+
+    fn check_boss_hit(&mut self) {
+        let Some(boss) = self.boss.as_mut() else {
+            return;
+        };
+        let boss_mask = self.collision_masks.get(&boss.texture).filter(|_| BOSS_PIXEL_COLLISION);
+
+        for (_, bullet) in self.player_bullets.iter_mut().filter(|(_, bullet)| !bullet.collided) {
+            if boss.is_destroyed() || bullet_impact(bullet, self.playfield, boss.position, boss.size(), boss_mask).is_none() {
+                continue;
+            }
+
+            // The boss is too big to pierce
+            bullet.collided = true;
+            count_hit(&mut self.score, bullet);
+            self.score.boss_hit(BOSS_HIT_POINTS);
+            if boss.hit() {
+                // Reward the player with a power-up for every new phase
+                let kind = PowerUpKind::ALL[self.rng.gen_range(0, PowerUpKind::ALL.len())];
+                let position = boss.position + (boss.size() - PowerUp::SIZE) / 2.0;
+                self.power_ups.push(PowerUp::new(kind, position));
+            }
+        }
+
+        if boss.is_destroyed() {
+            self.score.boss_hit(BOSS_BONUS * self.boss_number() as i32);
+            self.boss = None;
+            self.enemy_bullets.clear();
+        }
+    }
+
+    fn check_ufo_hit(&mut self) {
+        let Some(ufo) = self.ufo.as_mut() else {
+            return;
+        };
+
+        for (_, bullet) in self.player_bullets.iter_mut().filter(|(_, bullet)| !bullet.collided) {
+            if !ufo.collided && bullet.hits(self.playfield, ufo.position, Ufo::SIZE) {
+                bullet.collided = !bullet.piercing;
+                ufo.collided = true;
+                count_hit(&mut self.score, bullet);
+
+                let points = Ufo::SCORES[self.rng.gen_range(0, Ufo::SCORES.len())];
+                self.score.ufo_hit(points);
+                self.floating_texts.push(FloatingText::new(format!("+{points}"), bullet.position));
+            }
+        }
+
+        if ufo.collided {
+            self.ufo = None;
+        }
+    }
+
+    fn collect_power_ups(&mut self) {
+        for power_up in self.power_ups.iter_mut() {
+            let overlaps =
+                power_up.position.x < self.player.position.x + self.player.size().x &&
+                power_up.position.x + PowerUp::SIZE.x > self.player.position.x &&
+                power_up.position.y < self.player.position.y + self.player.size().y &&
+                power_up.position.y + PowerUp::SIZE.y > self.player.position.y;
+            if !overlaps {
+                continue;
+            }
+
+            power_up.collected = true;
+            match power_up.kind {
+                PowerUpKind::ExtraLife => self.player.lives = (self.player.lives + 1).min(MAX_LIVES),
+                kind => self.player.power_ups.activate(kind),
+            }
+        }
+        self.power_ups.retain(|power_up| !power_up.collected);
+    }
+
+    fn check_player_hit(&mut self) {
+        if self.player.is_invulnerable() {
+            return;
+        }
+
+        let player_position = self.player.position;
+        let player_size = self.player.size();
+        let player_mask = self.collision_masks.get(&self.player.texture).filter(|_| PLAYER_PIXEL_COLLISION);
+        let mut hit = false;
+        for (_, bullet) in self.enemy_bullets.iter_mut() {
+            if bullet_impact(bullet, self.playfield, player_position, player_size, player_mask).is_some() {
+                bullet.collided = true;
+                hit = true;
+            }
+        }
+        self.enemy_bullets.retain(|bullet| !bullet.collided);
+
+        // Crashing into a diving enemy destroys it, but without any points
+        for enemy in self.enemies.iter_mut().filter(|enemy| enemy.is_diving()) {
+            let position = enemy.position(&self.formation);
+            let size = enemy.size();
+            let overlaps =
+                position.x < player_position.x + player_size.x &&
+                position.x + size.x > player_position.x &&
+                position.y < player_position.y + player_size.y &&
+                position.y + size.y > player_position.y;
+            if overlaps {
+                enemy.collided = true;
+                hit = true;
+            }
+        }
+        self.enemies.retain(|enemy| !enemy.collided);
+
+        // The shield absorbs bullets and crashes without costing a life
+        if hit && !self.player.power_ups.is_active(PowerUpKind::Shield) {
+            self.player.hit(self.playfield);
+            self.score.player_hit();
+            // Give the respawned player a fair start
+            self.enemy_bullets.clear();
+        }
+    }
+
+    fn check_round_finished(&self) -> StepOutcome {
+        if self.player.lives == 0 {
+            return StepOutcome::PlayerDefeated;
+        }
+
+        if self.enemies.is_empty() && self.boss.is_none() {
+            return StepOutcome::LevelComplete;
+        }
+
+        // Diving enemies fly down to the player on purpose, only the formation must not get there
+        for enemy in self.enemies.iter().filter(|enemy| !enemy.is_diving()) {
+            if enemy.position(&self.formation).y + enemy.size().y >= self.player.position.y {
+                return StepOutcome::PlayerDefeated;
+            }
+        }
+
+        StepOutcome::Running
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dive::DivePattern;
+    use crate::timestep::FixedTimestep;
+
+    const ENEMY_TEXTURE: &str = "enemy.png";
+
+    /// A level with a single row of enemies which neither shoot nor dive
+    fn level(row: &str, hit_points: u32) -> Level {
+        serde_json::from_value(serde_json::json!({
+            "name": "Test",
+            "texture": ENEMY_TEXTURE,
+            "speed_multiplier": 1.0,
+            "fire_rate": 0.0,
+            "enemy_types": { "E": { "texture": ENEMY_TEXTURE, "hit_points": hit_points } },
+            "formation": [row],
+        }))
+        .unwrap()
+    }
+
+    fn world(levels: Vec<Level>) -> World {
+        let texture_sizes = HashMap::from([
+            (PLAYER_TEXTURE.to_string(), vec2(64.0, 64.0)),
+            (ENEMY_TEXTURE.to_string(), vec2(64.0, 48.0)),
+        ]);
+        let mut world = World::new(PLAYFIELD, texture_sizes, HashMap::new(), levels);
+        world.new_game(1, 1, Difficulty::Normal);
+        world
+    }
+
+    /// Fires a player bullet from just below the centre of the enemy at `index`
+    fn fire_at(world: &mut World, index: usize, offset: f32, piercing: bool) {
+        let enemy = &world.enemies[index];
+        let position = enemy.position(&world.formation) + vec2(enemy.size().x / 2.0 + offset, enemy.size().y + 1.0);
+        let mut bullet = Bullet::new(position, Owner::Player);
+        bullet.piercing = piercing;
+        world.player_bullets.insert(bullet).unwrap();
+    }
+
+    /// Steps until all player bullets are gone or the level ends
+    fn run_bullets(world: &mut World) -> StepOutcome {
+        for _ in 0..1000 {
+            let outcome = world.step(&Input::default(), FixedTimestep::TICK);
+            if outcome != StepOutcome::Running || world.player_bullets.iter().next().is_none() {
+                return outcome;
+            }
+        }
+        panic!("the bullets never left the playfield");
+    }
+
+    #[test]
+    fn shooting_the_last_enemy_completes_the_level() {
+        let mut world = world(vec![level("E.E", 1)]);
+        fire_at(&mut world, 0, 0.0, false);
+        assert_eq!(run_bullets(&mut world), StepOutcome::Running);
+        assert_eq!(world.enemies.len(), 1);
+
+        fire_at(&mut world, 0, 0.0, false);
+        assert_eq!(run_bullets(&mut world), StepOutcome::LevelComplete);
+        assert!(world.enemies.is_empty());
+    }
+
+    #[test]
+    fn losing_the_last_life_defeats_the_player() {
+        let mut world = world(vec![level("E", 1)]);
+        for lives in (0..Player::START_LIVES).rev() {
+            // Wait until the player can be hit again after respawning
+            while world.player.is_invulnerable() {
+                assert_eq!(world.step(&Input::default(), FixedTimestep::TICK), StepOutcome::Running);
+            }
+            let player = &world.player;
+            let position = player.position + vec2(player.size().x / 2.0, -1.0);
+            world.enemy_bullets.insert(Bullet::new(position, Owner::Enemy)).unwrap();
+
+            let outcome = world.step(&Input::default(), FixedTimestep::TICK);
+            assert_eq!(world.player.lives, lives);
+            let expected = if lives == 0 { StepOutcome::PlayerDefeated } else { StepOutcome::Running };
+            assert_eq!(outcome, expected);
+        }
+    }
+
+    /// Everything that shows whether two worlds played out the same
+    fn snapshot(world: &World) -> String {
+        let enemies: Vec<Vec2> = world.enemies.iter().map(|enemy| enemy.position(&world.formation)).collect();
+        let player_bullets: Vec<Vec2> = world.player_bullets.iter().map(|bullet| bullet.position).collect();
+        let enemy_bullets: Vec<Vec2> = world.enemy_bullets.iter().map(|bullet| bullet.position).collect();
+        let ufo = world.ufo.as_ref().map(|ufo| ufo.position);
+        format!(
+            "{} {} {} {:?} {:?} {:?} {:?} {:?}",
+            world.level, world.score.total, world.player.lives, world.player.position, enemies, player_bullets, enemy_bullets, ufo,
+        )
+    }
+
+    #[test]
+    fn same_seed_and_inputs_play_out_the_same() {
+        let mut level = level("EEEEEEEEEE", 2);
+        level.fire_rate = 2.0;
+        level.dive_patterns = vec![DivePattern::Swoop, DivePattern::Zigzag];
+        level.dive_rate = 0.5;
+        let mut worlds = [world(vec![level.clone()]), world(vec![level])];
+        for world in worlds.iter_mut() {
+            world.new_game(1234, 1, Difficulty::Hard);
+        }
+
+        for tick in 0..20 * 120 {
+            // Sweep from side to side while firing most of the time
+            let input = Input { left: tick / 90 % 2 == 0, right: tick / 90 % 2 == 1, fire: tick % 7 != 0, escape: false };
+            let outcomes = worlds.each_mut().map(|world| world.step(&input, FixedTimestep::TICK));
+            assert_eq!(outcomes[0], outcomes[1]);
+            match outcomes[0] {
+                StepOutcome::Running => {}
+                StepOutcome::LevelComplete => worlds.iter_mut().for_each(World::next_level),
+                StepOutcome::PlayerDefeated => break,
+            }
+        }
+
+        assert_eq!(snapshot(&worlds[0]), snapshot(&worlds[1]));
+        assert!(worlds[0].score.total > 0);
+    }
+
+    #[test]
+    fn the_level_counter_stops_at_its_maximum() {
+        let mut world = world(vec![level("E", 1), level("EE", 1)]);
+        world.new_game(1, i8::MAX, Difficulty::Normal);
+        world.next_level();
+        assert_eq!(world.level, i8::MAX);
+        assert_eq!(world.enemies.len(), 1);
+    }
+
+    #[test]
+    fn overlapping_piercing_bullets_hit_once_each() {
+        let mut world = world(vec![level("E", 3)]);
+        fire_at(&mut world, 0, -2.0, true);
+        fire_at(&mut world, 0, 2.0, true);
+
+        assert_eq!(run_bullets(&mut world), StepOutcome::Running);
+        assert_eq!(world.enemies.len(), 1);
+        assert_eq!(world.enemies[0].hit_points, 1);
+        assert!(world.enemies[0].pierced_by.is_empty());
+    }
+}
@@ -0,0 +1,170 @@
+//! Plays clients against a leaderboard server on a free port of localhost.
+//!
+//! Run with `cargo test`, the server is started in the test process itself.
+use std::env;
+use std::fs;
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::process;
+use std::thread;
+use tiny_http::Server;
+use codeinvaders::game_mode::{Difficulty, GameMode};
+use codeinvaders::high_scores::{GameStats, HighScores, LeaderboardKey, ServerStatus};
+use codeinvaders::leaderboard_server;
+
+/// An empty directory for the high scores of one client or server
+fn data_dir(name: &str) -> PathBuf {
+    let directory = env::temp_dir().join(format!("codeinvaders-test-{}-{name}", process::id()));
+    let _ = fs::remove_dir_all(&directory);
+    directory
+}
+
+/// Starts a server on a free port and returns its URL
+fn start_server(name: &str, table_length: usize) -> String {
+    let server = Server::http("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", server.server_addr());
+
+    let mut high_scores = HighScores::new(&data_dir(name), table_length);
+    high_scores.load().unwrap();
+    thread::spawn(move || leaderboard_server::run(&server, &mut high_scores));
+    url
+}
+
+fn key(difficulty: Difficulty) -> LeaderboardKey {
+    LeaderboardKey { mode: GameMode::Classic, difficulty, level_set: "levels".to_string() }
+}
+
+#[test]
+fn scores_are_shared_between_clients() {
+    let url = start_server("shared", 10);
+
+    let alice_directory = data_dir("shared-alice");
+    let mut alice = HighScores::new(&alice_directory, 10);
+    alice.connect(&url);
+    alice.wait();
+    alice.add_score(&key(Difficulty::Normal), "alice".to_string(), 300, GameStats::default(), "a".to_string());
+    alice.wait();
+
+    let mut bob = HighScores::new(&data_dir("shared-bob"), 10);
+    bob.connect(&url);
+    assert_eq!(bob.server_status(), ServerStatus::Syncing);
+    bob.wait();
+    assert_eq!(bob.server_status(), ServerStatus::Connected);
+    assert_eq!(bob.board_title(0), "CLASSIC - NORMAL - LEVELS");
+    assert_eq!(bob.display(0), vec!["1: alice - 300"]);
+
+    bob.add_score(&key(Difficulty::Normal), "bob".to_string(), 400, GameStats::default(), "b".to_string());
+    bob.wait();
+    bob.add_score(&key(Difficulty::Hard), "bob".to_string(), 100, GameStats::default(), "c".to_string());
+    bob.wait();
+    assert_eq!(bob.display(0), vec!["1: bob - 400", "2: alice - 300"]);
+    assert_eq!(bob.board_count(), 2);
+
+    // Every client keeps its own scores locally as well
+    let mut local = HighScores::new(&alice_directory, 10);
+    local.load().unwrap();
+    assert_eq!(local.display(0), vec!["1: alice - 300"]);
+}
+
+#[test]
+fn top_scores_are_limited() {
+    let url = start_server("limit", 3);
+
+    let mut client = HighScores::new(&data_dir("limit-client"), 10);
+    client.connect(&url);
+    for score in 1..=5 {
+        client.add_score(&key(Difficulty::Easy), format!("player {score}"), score * 10, GameStats::default(), score.to_string());
+        client.wait();
+    }
+    // The server keeps three entries per board
+    assert_eq!(client.display(0), vec!["1: player 5 - 50", "2: player 4 - 40", "3: player 3 - 30"]);
+
+    let mut short = HighScores::new(&data_dir("limit-short"), 2);
+    short.connect(&url);
+    short.wait();
+    assert_eq!(short.len(0), 2);
+}
+
+#[test]
+fn scores_missing_the_server_board_are_saved_locally() {
+    let url = start_server("local", 2);
+
+    let mut alice = HighScores::new(&data_dir("local-alice"), 2);
+    alice.connect(&url);
+    for (score, hash) in [(100, "a"), (200, "b")] {
+        alice.add_score(&key(Difficulty::Normal), "alice".to_string(), score, GameStats::default(), hash.to_string());
+        alice.wait();
+    }
+    // Both boards of alice are full of better scores
+    assert!(!alice.qualifies(&key(Difficulty::Normal), 100));
+    assert!(alice.qualifies(&key(Difficulty::Normal), 101));
+
+    let bob_directory = data_dir("local-bob");
+    let mut bob = HighScores::new(&bob_directory, 2);
+    bob.connect(&url);
+    bob.wait();
+    assert!(bob.qualifies(&key(Difficulty::Normal), 50));
+    bob.add_score(&key(Difficulty::Normal), "bob".to_string(), 50, GameStats::default(), "c".to_string());
+    bob.wait();
+    assert_eq!(bob.display(0), vec!["1: alice - 200", "2: alice - 100"]);
+
+    let mut local = HighScores::new(&bob_directory, 2);
+    local.load().unwrap();
+    assert_eq!(local.display(0), vec!["1: bob - 50"]);
+}
+
+#[test]
+fn forged_scores_are_rejected() {
+    let url = start_server("forged", 10);
+
+    let submission = serde_json::json!({
+        "key": { "mode": "classic", "difficulty": "normal", "level_set": "levels" },
+        "entry": { "name": "mallory", "score": 999999, "replay_hash": "", "signature": "00" },
+    });
+    let response = ureq::post(&format!("{url}/scores")).send_string(&submission.to_string());
+    assert!(matches!(response, Err(ureq::Error::Status(403, _))));
+
+    let mut client = HighScores::new(&data_dir("forged-client"), 10);
+    client.connect(&url);
+    client.wait();
+    assert_eq!(client.board_count(), 0);
+}
+
+#[test]
+fn duplicate_scores_are_rejected() {
+    let url = start_server("duplicate", 10);
+
+    let mut client = HighScores::new(&data_dir("duplicate-client"), 10);
+    client.connect(&url);
+    client.add_score(&key(Difficulty::Normal), "alice".to_string(), 300, GameStats::default(), "a".to_string());
+    client.wait();
+
+    // Sending the signed entry of the same game again
+    let response = ureq::get(&format!("{url}/scores")).call().unwrap();
+    let boards: serde_json::Value = serde_json::from_reader(response.into_reader()).unwrap();
+    let submission = serde_json::json!({ "key": boards[0]["key"], "entry": boards[0]["entries"][0] });
+    let response = ureq::post(&format!("{url}/scores")).send_string(&submission.to_string());
+    assert!(matches!(response, Err(ureq::Error::Status(409, _))));
+
+    client.connect(&url);
+    client.wait();
+    assert_eq!(client.display(0), vec!["1: alice - 300"]);
+}
+
+#[test]
+fn unreachable_server_falls_back_to_local_scores() {
+    // Nothing listens on a port which was just released
+    let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    let directory = data_dir("offline");
+
+    let mut client = HighScores::new(&directory, 10);
+    client.load().unwrap();
+    client.connect(&format!("http://127.0.0.1:{port}"));
+    client.wait();
+    assert_eq!(client.server_status(), ServerStatus::Unreachable);
+
+    // The local entry is there right away, without waiting for the server
+    client.add_score(&key(Difficulty::Normal), "offline".to_string(), 50, GameStats::default(), "d".to_string());
+    assert_eq!(client.display(0), vec!["1: offline - 50"]);
+    assert!(directory.join(HighScores::FILE_NAME).exists());
+}